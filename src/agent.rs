@@ -0,0 +1,89 @@
+//! An environment for reinforcement learning agents, in the usual shape:
+//! `reset` starts an episode, and `step` takes a command and returns what
+//! the agent sees, its reward, and whether the episode is over.
+//!
+//! The reward is the points scored, so reaching the other world, unearthing
+//! the sword, getting past the guard, opening the case and winning each pay.
+
+use super::{Session, World};
+
+pub struct Environment {
+    world: &'static World,
+    session: Session
+}
+
+/// What came of a command.
+pub struct Step {
+    /// What the game printed.
+    pub observation: String,
+    /// Points for the milestones reached. Negative if RESTART or RESTORE lost some.
+    pub reward: i32,
+    /// Won, dead or quit, after which it's time to `reset`.
+    pub done: bool,
+    /// Commands the parser would act on now, to choose from.
+    pub commands: Vec<String>
+}
+
+impl Environment {
+    pub fn new(world: &'static World) -> Self {
+        Environment { world, session: Session::new(world, false) }
+    }
+
+    /// Starts a new game. The observation is its introduction.
+    pub fn reset(&mut self) -> Step {
+        self.session = Session::new(self.world, false);
+        let observation = self.session.take_output();
+        self.observe(observation, 0)
+    }
+
+    pub fn step(&mut self, command: &str) -> Step {
+        let score = self.session.score();
+        let observation = self.session.step(command);
+        let reward = self.session.score() as i32 - score as i32;
+        self.observe(observation, reward)
+    }
+
+    fn observe(&self, observation: String, reward: i32) -> Step {
+        let session = &self.session;
+        Step {
+            observation,
+            reward,
+            done: session.won() || session.dead() || session.is_over(),
+            commands: session.commands()
+        }
+    }
+
+    /// The game underneath, to look into.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STOCK;
+
+    #[test]
+    fn points_scored_are_the_reward() {
+        let mut env = Environment::new(&STOCK);
+        let start = env.reset();
+        assert!(start.observation.contains("UNCLE SIMON"));
+        assert!(start.commands.contains(&"OPEN BOX".to_string()));
+        let rewards: Vec<i32> = ["OPEN BOX", "GET BOTTLE", "E", "OPEN CABINET", "GET SALT", "W", "N", "W",
+                                 "POUR SALT", "POUR BOTTLE"]
+            .iter()
+            .map(|command| env.step(command).reward)
+            .collect();
+        let scored = env.session().score() as i32;
+        assert!(scored > 0);
+        assert_eq!(rewards.iter().sum::<i32>(), scored);
+        assert_eq!(rewards.last(), Some(&scored));
+
+        let restart = env.step("RESTART");
+        assert_eq!(restart.reward, -scored);
+        assert!(!restart.done);
+        assert!(env.step("QUIT").done);
+        assert!(!env.reset().done);
+    }
+}
@@ -0,0 +1,1047 @@
+//! A small Microsoft BASIC, enough to run the book's listings as typed in.
+//!
+//! It follows the 8-bit dialects where they agree: keywords are picked out
+//! even without spaces around them, only the first two letters of a variable
+//! name count, a false IF skips the rest of its line, and a FOR loop always
+//! runs at least once. PEEK, POKE and the screen commands do nothing.
+//! Input is upper-cased, as if the caps lock was on.
+
+use std::collections::HashMap;
+use std::io;
+
+use super::import::{split_outside_quotes, unquote};
+use super::Terminal;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Keyword {
+    End, For, Next, Data, Input, Dim, Read, Let, Goto, Run, If, Restore, Gosub, Return,
+    Rem, Stop, On, Print, Cls, Home, Poke, To, Then, Step, And, Or, Not, Tab, Spc,
+    Left, Right, Mid, Len, Chr, Asc, Val, Str, Int, Abs, Sgn, Rnd, Peek, Sqr
+}
+
+use self::Keyword::*;
+
+static KEYWORDS: [(&str, Keyword); 43] = [
+    ("RESTORE", Restore), ("RETURN", Return), ("RIGHT$", Right),
+    ("GOSUB", Gosub), ("INPUT", Input), ("LEFT$", Left), ("PRINT", Print),
+    ("CHR$", Chr), ("DATA", Data), ("GOTO", Goto), ("HOME", Home), ("MID$", Mid),
+    ("NEXT", Next), ("PEEK", Peek), ("POKE", Poke), ("READ", Read), ("SPC(", Spc),
+    ("STEP", Step), ("STOP", Stop), ("STR$", Str), ("TAB(", Tab), ("THEN", Then),
+    ("ABS", Abs), ("AND", And), ("ASC", Asc), ("CLS", Cls), ("DIM", Dim), ("END", End),
+    ("FOR", For), ("INT", Int), ("LEN", Len), ("LET", Let), ("NOT", Not), ("REM", Rem),
+    ("RND", Rnd), ("RUN", Run), ("SGN", Sgn), ("SQR", Sqr), ("VAL", Val),
+    ("IF", If), ("ON", On), ("OR", Or), ("TO", To),
+];
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Kw(Keyword),
+    Num(f64),
+    Str(String),
+    Name(String),
+    Op(&'static str),
+    /// The items of a DATA statement, already split and unquoted.
+    Data(Vec<String>),
+    /// Anything else, which is a syntax error if it's ever run.
+    Other(char)
+}
+
+static OPS: [&str; 16] = [
+    "<>", "><", "<=", "=<", ">=", "=>", "+", "-", "*", "/", "^", "=", "<", ">", "(", ")"
+];
+
+fn keyword_at(text: &str) -> Option<(&'static str, Keyword)> {
+    KEYWORDS.iter().copied().find(|(word, _)| {
+        text.get(..word.len()).is_some_and(|start| start.eq_ignore_ascii_case(word))
+    })
+}
+
+/// Crunches a line the way the interpreters did as it was typed in.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        if c == ' ' {
+            i += 1;
+        } else if c == '"' {
+            let end = rest[1..].find('"').map_or(rest.len(), |end| end + 2);
+            tokens.push(Token::Str(rest[1..end].trim_end_matches('"').to_string()));
+            i += end;
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut end = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+            if rest[end..].starts_with(['E', 'e']) {
+                let exp = &rest[end + 1..];
+                let sign = usize::from(exp.starts_with(['+', '-']));
+                let digits = exp[sign..].find(|c: char| !c.is_ascii_digit()).unwrap_or(exp.len() - sign);
+                if digits > 0 {
+                    end += 1 + sign + digits;
+                }
+            }
+            tokens.push(Token::Num(rest[..end].parse().unwrap_or(0.0)));
+            i += end;
+        } else if let Some((word, keyword)) = keyword_at(rest) {
+            i += word.len();
+            match keyword {
+                Rem => break,
+                Data => {
+                    let raw = &text[i..];
+                    let end = split_outside_quotes(raw, ':')[0].len();
+                    tokens.push(Token::Data(split_outside_quotes(&raw[..end], ',')
+                        .into_iter().map(unquote).collect()));
+                    i += end;
+                }
+                _ => tokens.push(Token::Kw(keyword))
+            }
+        } else if c.is_ascii_alphabetic() {
+            let mut end = 1;
+            while rest[end..].starts_with(|c: char| c.is_ascii_alphanumeric())
+                && keyword_at(&rest[end..]).is_none()
+            {
+                end += 1;
+            }
+            if rest[end..].starts_with('$') {
+                end += 1;
+            }
+            tokens.push(Token::Name(rest[..end].to_ascii_uppercase()));
+            i += end;
+        } else if c == '?' {
+            tokens.push(Token::Kw(Print));
+            i += 1;
+        } else if c == ':' || c == ';' || c == ',' {
+            tokens.push(Token::Op(match c { ':' => ":", ';' => ";", _ => "," }));
+            i += 1;
+        } else if let Some(op) = OPS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            i += op.len();
+        } else {
+            tokens.push(Token::Other(c));
+            i += c.len_utf8();
+        }
+    }
+    tokens
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Value {
+    Num(f64),
+    Str(String)
+}
+
+struct Line {
+    number: u32,
+    tokens: Vec<Token>
+}
+
+/// Where the interpreter is: a line and a token in it.
+#[derive(Clone, Copy)]
+struct Pos {
+    line: usize,
+    token: usize
+}
+
+/// GOSUB and FOR share one stack, so RETURN drops the loops it leaves.
+enum Frame {
+    Gosub(Pos),
+    For { var: String, limit: f64, step: f64, body: Pos }
+}
+
+struct Array {
+    dims: Vec<usize>,
+    values: Vec<Value>
+}
+
+/// Why the program stopped running.
+enum Halt {
+    End,
+    Error(&'static str),
+    Io(io::Error)
+}
+
+impl From<io::Error> for Halt {
+    fn from(err: io::Error) -> Self {
+        Halt::Io(err)
+    }
+}
+
+type Run<T> = Result<T, Halt>;
+
+const SYNTAX: Halt = Halt::Error("SYNTAX");
+const TYPE_MISMATCH: Halt = Halt::Error("TYPE MISMATCH");
+const ILLEGAL_QUANTITY: Halt = Halt::Error("ILLEGAL QUANTITY");
+const OUT_OF_MEMORY: Halt = Halt::Error("OUT OF MEMORY");
+
+/// Print zones for commas, as on most Microsoft BASICs.
+const ZONE: usize = 14;
+
+/// Arrays used without a DIM get 11 elements, 0 to 10.
+const DEFAULT_DIM: usize = 10;
+
+/// GOSUBs and FOR loops open at once. Far more than any program needs,
+/// but a GOSUB that never returns runs out, as it did on the machines.
+const MAX_STACK: usize = 256;
+
+struct Basic<'a> {
+    lines: Vec<Line>,
+    data: Vec<String>,
+    next_data: usize,
+    vars: HashMap<String, Value>,
+    arrays: HashMap<String, Array>,
+    stack: Vec<Frame>,
+    pos: Pos,
+    /// Set by statements that leave `pos` somewhere other than their end.
+    jumped: bool,
+    column: usize,
+    seed: u32,
+    term: &'a mut dyn Terminal
+}
+
+/// Runs a listing until it ENDs, fails or runs out of input.
+pub fn run(listing: &str, term: &mut dyn Terminal) -> io::Result<()> {
+    let mut lines: Vec<Line> = Vec::new();
+    for text in listing.lines() {
+        let text = text.trim();
+        let digits = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+        let number = match text[..digits].parse() {
+            Ok(number) => number,
+            Err(_) => continue
+        };
+        let line = Line { number, tokens: tokenize(&text[digits..]) };
+        match lines.binary_search_by_key(&number, |line| line.number) {
+            Ok(i) => lines[i] = line,
+            Err(i) => lines.insert(i, line)
+        }
+    }
+    let data = lines.iter()
+        .flat_map(|line| &line.tokens)
+        .filter_map(|token| match token {
+            Token::Data(items) => Some(items.clone()),
+            _ => None
+        })
+        .flatten()
+        .collect();
+
+    let mut basic = Basic {
+        lines,
+        data,
+        next_data: 0,
+        vars: HashMap::new(),
+        arrays: HashMap::new(),
+        stack: Vec::new(),
+        pos: Pos { line: 0, token: 0 },
+        jumped: false,
+        column: 0,
+        seed: 1,
+        term
+    };
+    let halt = loop {
+        if basic.pos.line >= basic.lines.len() {
+            break Halt::End;
+        }
+        if let Err(halt) = basic.step() {
+            break halt;
+        }
+    };
+    match halt {
+        Halt::End => Ok(()),
+        Halt::Io(err) => Err(err),
+        Halt::Error(msg) => {
+            let number = basic.lines[basic.pos.line].number;
+            let newline = if basic.column > 0 { "\n" } else { "" };
+            basic.term.print(&format!("{}?{} ERROR IN {}\n", newline, msg, number))
+        }
+    }
+}
+
+/// Only the first two letters of a name count, plus the $ of a string.
+fn var_key(name: &str) -> String {
+    let string = name.ends_with('$');
+    let mut key: String = name.trim_end_matches('$').chars().take(2).collect();
+    if string {
+        key.push('$');
+    }
+    key
+}
+
+fn format_number(n: f64) -> String {
+    if n == n.trunc() && n.abs() < 1e9 {
+        return format!("{}", n as i64);
+    }
+    let magnitude = n.abs().log10().floor() as i32 + 1;
+    let decimals = (9 - magnitude).clamp(0, 9) as usize;
+    let s = format!("{:.*}", decimals, n);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    s.replacen("0.", ".", 1)
+}
+
+/// The leading space is where the sign would go.
+fn str_number(n: f64) -> String {
+    if n < 0.0 {
+        format_number(n)
+    } else {
+        format!(" {}", format_number(n))
+    }
+}
+
+/// A number as typed into INPUT or a DATA statement. Blank is 0.
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Some(0.0);
+    }
+    s.parse().ok()
+}
+
+fn truth(b: bool) -> Value {
+    Value::Num(if b { -1.0 } else { 0.0 })
+}
+
+impl Basic<'_> {
+    fn step(&mut self) -> Run<()> {
+        if self.pos.token == 0 {
+            let number = self.lines[self.pos.line].number;
+            self.term.trace(number);
+        }
+        match self.peek() {
+            None => {
+                self.pos = Pos { line: self.pos.line + 1, token: 0 };
+                return Ok(());
+            }
+            Some(Token::Op(":")) => {
+                self.pos.token += 1;
+                return Ok(());
+            }
+            _ => {}
+        }
+        self.jumped = false;
+        self.statement()?;
+        if !self.jumped && !self.at_end() {
+            return Err(SYNTAX);
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.lines[self.pos.line].tokens.get(self.pos.token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.pos.token += 1;
+        token
+    }
+
+    fn at_end(&self) -> bool {
+        matches!(self.peek(), None | Some(Token::Op(":")))
+    }
+
+    fn accept(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(o)) if *o == op) {
+            self.pos.token += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Run<()> {
+        if self.accept(op) { Ok(()) } else { Err(SYNTAX) }
+    }
+
+    fn accept_keyword(&mut self, keyword: Keyword) -> bool {
+        if self.peek() == Some(&Token::Kw(keyword)) {
+            self.pos.token += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn print(&mut self, text: &str) -> Run<()> {
+        self.column = match text.rfind('\n') {
+            Some(i) => text.len() - i - 1,
+            None => self.column + text.len()
+        };
+        Ok(self.term.print(text)?)
+    }
+
+    fn goto(&mut self, number: u32) -> Run<()> {
+        let line = self.lines.binary_search_by_key(&number, |line| line.number)
+            .map_err(|_| Halt::Error("UNDEF'D STATEMENT"))?;
+        self.pos = Pos { line, token: 0 };
+        self.jumped = true;
+        Ok(())
+    }
+
+    fn line_number(&mut self) -> Run<u32> {
+        match self.next() {
+            Some(Token::Num(n)) if n >= 0.0 && n == n.trunc() => Ok(n as u32),
+            _ => Err(SYNTAX)
+        }
+    }
+
+    fn statement(&mut self) -> Run<()> {
+        match self.next().ok_or(SYNTAX)? {
+            Token::Kw(Let) => self.assignment(),
+            Token::Name(_) => {
+                self.pos.token -= 1;
+                self.assignment()
+            }
+            Token::Kw(Print) => self.print_statement(),
+            Token::Kw(Input) => self.input(),
+            Token::Kw(If) => {
+                let condition = self.num()?;
+                if self.accept_keyword(Goto) || self.accept_keyword(Then) {
+                    if condition == 0.0 {
+                        self.pos = Pos { line: self.pos.line + 1, token: 0 };
+                        self.jumped = true;
+                    } else if let Some(Token::Num(_)) = self.peek() {
+                        let number = self.line_number()?;
+                        self.goto(number)?;
+                    } else {
+                        // carry on with the statements after THEN
+                        self.jumped = true;
+                    }
+                    Ok(())
+                } else {
+                    Err(SYNTAX)
+                }
+            }
+            Token::Kw(Goto) => {
+                let number = self.line_number()?;
+                self.goto(number)
+            }
+            Token::Kw(Gosub) => {
+                let number = self.line_number()?;
+                self.push(Frame::Gosub(self.pos))?;
+                self.goto(number)
+            }
+            Token::Kw(Return) => {
+                loop {
+                    match self.stack.pop() {
+                        Some(Frame::Gosub(pos)) => break self.pos = pos,
+                        Some(Frame::For { .. }) => {}
+                        None => return Err(Halt::Error("RETURN WITHOUT GOSUB"))
+                    }
+                }
+                self.jumped = true;
+                Ok(())
+            }
+            Token::Kw(On) => {
+                let index = self.num()?;
+                if index < 0.0 {
+                    return Err(ILLEGAL_QUANTITY);
+                }
+                let gosub = match self.next() {
+                    Some(Token::Kw(Goto)) => false,
+                    Some(Token::Kw(Gosub)) => true,
+                    _ => return Err(SYNTAX)
+                };
+                let mut targets = vec![self.line_number()?];
+                while self.accept(",") {
+                    targets.push(self.line_number()?);
+                }
+                match targets.get((index as usize).wrapping_sub(1)) {
+                    Some(&number) => {
+                        if gosub {
+                            self.push(Frame::Gosub(self.pos))?;
+                        }
+                        self.goto(number)
+                    }
+                    None => Ok(())
+                }
+            }
+            Token::Kw(For) => self.for_statement(),
+            Token::Kw(Next) => self.next_statement(),
+            Token::Kw(Dim) => {
+                loop {
+                    let name = match self.next() {
+                        Some(Token::Name(name)) => var_key(&name),
+                        _ => return Err(SYNTAX)
+                    };
+                    self.expect("(")?;
+                    let dims = self.subscripts()?;
+                    if self.arrays.contains_key(&name) {
+                        return Err(Halt::Error("REDIM'D ARRAY"));
+                    }
+                    self.dim(name, dims);
+                    if !self.accept(",") {
+                        return Ok(());
+                    }
+                }
+            }
+            Token::Kw(Read) => {
+                loop {
+                    let target = self.target()?;
+                    let item = self.data.get(self.next_data).cloned()
+                        .ok_or(Halt::Error("OUT OF DATA"))?;
+                    self.next_data += 1;
+                    let value = if target.0.ends_with('$') {
+                        Value::Str(item)
+                    } else {
+                        Value::Num(parse_number(&item).ok_or(SYNTAX)?)
+                    };
+                    self.assign(target, value)?;
+                    if !self.accept(",") {
+                        return Ok(());
+                    }
+                }
+            }
+            Token::Kw(Restore) => {
+                self.next_data = 0;
+                Ok(())
+            }
+            Token::Kw(Data) | Token::Data(_) => Ok(()),
+            Token::Kw(Poke) => {
+                self.num()?;
+                self.expect(",")?;
+                self.num()?;
+                Ok(())
+            }
+            Token::Kw(Cls) | Token::Kw(Home) => Ok(()),
+            Token::Kw(End) => Err(Halt::End),
+            Token::Kw(Stop) => {
+                let number = self.lines[self.pos.line].number;
+                self.print(&format!("\nBREAK IN {}\n", number))?;
+                Err(Halt::End)
+            }
+            Token::Kw(Run) => {
+                self.vars.clear();
+                self.arrays.clear();
+                self.stack.clear();
+                self.next_data = 0;
+                self.pos = Pos { line: 0, token: 0 };
+                self.jumped = true;
+                Ok(())
+            }
+            _ => Err(SYNTAX)
+        }
+    }
+
+    fn print_statement(&mut self) -> Run<()> {
+        let mut newline = true;
+        while !self.at_end() {
+            if self.accept(";") {
+                newline = false;
+            } else if self.accept(",") {
+                let spaces = ZONE - self.column % ZONE;
+                self.print(&" ".repeat(spaces))?;
+                newline = false;
+            } else if self.accept_keyword(Tab) || self.accept_keyword(Spc) {
+                let tab = self.lines[self.pos.line].tokens[self.pos.token - 1] == Token::Kw(Tab);
+                let n = self.num()?;
+                self.expect(")")?;
+                if !(0.0..256.0).contains(&n) {
+                    return Err(ILLEGAL_QUANTITY);
+                }
+                let spaces = if tab { (n as usize).saturating_sub(self.column) } else { n as usize };
+                self.print(&" ".repeat(spaces))?;
+                newline = false;
+            } else {
+                let text = match self.expr()? {
+                    Value::Str(s) => s,
+                    Value::Num(n) => str_number(n) + " "
+                };
+                self.print(&text)?;
+                newline = true;
+            }
+        }
+        if newline {
+            self.print("\n")?;
+        }
+        Ok(())
+    }
+
+    fn input(&mut self) -> Run<()> {
+        let mut prompt = String::new();
+        if let Some(Token::Str(s)) = self.peek() {
+            prompt = s.clone();
+            self.pos.token += 1;
+            if !self.accept(";") {
+                self.expect(",")?;
+            }
+        }
+        let mut targets = vec![self.target()?];
+        while self.accept(",") {
+            targets.push(self.target()?);
+        }
+
+        'redo: loop {
+            let mut fields = Vec::new();
+            let mut question = format!("{}? ", prompt);
+            while fields.len() < targets.len() {
+                self.print(&question)?;
+                let line = self.term.read_line()?.ok_or(Halt::End)?.to_ascii_uppercase();
+                self.column = 0;
+                fields.extend(split_outside_quotes(&line, ',').into_iter().map(unquote));
+                question = "?? ".to_string();
+            }
+            let mut values = Vec::new();
+            for (target, field) in targets.iter().zip(&fields) {
+                if target.0.ends_with('$') {
+                    values.push(Value::Str(field.clone()));
+                } else {
+                    match parse_number(field) {
+                        Some(n) => values.push(Value::Num(n)),
+                        None => {
+                            self.print("?REDO FROM START\n")?;
+                            continue 'redo;
+                        }
+                    }
+                }
+            }
+            if fields.len() > targets.len() {
+                self.print("?EXTRA IGNORED\n")?;
+            }
+            for (target, value) in targets.into_iter().zip(values) {
+                self.assign(target, value)?;
+            }
+            return Ok(());
+        }
+    }
+
+    fn for_statement(&mut self) -> Run<()> {
+        let var = match self.next() {
+            Some(Token::Name(name)) if !name.ends_with('$') => var_key(&name),
+            _ => return Err(SYNTAX)
+        };
+        self.expect("=")?;
+        let start = self.num()?;
+        if !self.accept_keyword(To) {
+            return Err(SYNTAX);
+        }
+        let limit = self.num()?;
+        let step = if self.accept_keyword(Step) { self.num()? } else { 1.0 };
+        if !self.at_end() {
+            return Err(SYNTAX);
+        }
+        self.vars.insert(var.clone(), Value::Num(start));
+
+        // starting a loop again drops it and any loops inside it
+        let mut i = self.stack.len();
+        while let Some(Frame::For { var: v, .. }) = i.checked_sub(1).map(|j| &self.stack[j]) {
+            i -= 1;
+            if *v == var {
+                self.stack.truncate(i);
+                break;
+            }
+        }
+        self.push(Frame::For { var, limit, step, body: self.pos })
+    }
+
+    fn push(&mut self, frame: Frame) -> Run<()> {
+        if self.stack.len() >= MAX_STACK {
+            return Err(OUT_OF_MEMORY);
+        }
+        self.stack.push(frame);
+        Ok(())
+    }
+
+    fn next_statement(&mut self) -> Run<()> {
+        loop {
+            let name = match self.peek() {
+                Some(Token::Name(name)) => {
+                    let name = var_key(name);
+                    self.pos.token += 1;
+                    Some(name)
+                }
+                _ => None
+            };
+            let (var, limit, step, body) = loop {
+                match self.stack.last() {
+                    Some(Frame::For { var, limit, step, body }) => {
+                        if name.as_ref().is_none_or(|name| name == var) {
+                            break (var.clone(), *limit, *step, *body);
+                        }
+                        self.stack.pop();
+                    }
+                    _ => return Err(Halt::Error("NEXT WITHOUT FOR"))
+                }
+            };
+            let value = match self.vars.get(&var) {
+                Some(Value::Num(n)) => n + step,
+                _ => step
+            };
+            self.vars.insert(var, Value::Num(value));
+            if (step >= 0.0 && value <= limit) || (step < 0.0 && value >= limit) {
+                self.pos = body;
+                self.jumped = true;
+                return Ok(());
+            }
+            self.stack.pop();
+            if !self.accept(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn dim(&mut self, name: String, dims: Vec<usize>) {
+        let size = dims.iter().map(|dim| dim + 1).product();
+        let empty = if name.ends_with('$') { Value::Str(String::new()) } else { Value::Num(0.0) };
+        self.arrays.insert(name, Array { dims, values: vec![empty; size] });
+    }
+
+    /// The subscripts after an array's opening bracket, and the closing one.
+    fn subscripts(&mut self) -> Run<Vec<usize>> {
+        let mut subscripts = Vec::new();
+        loop {
+            let n = self.num()?;
+            if !(0.0..32768.0).contains(&n) {
+                return Err(Halt::Error("BAD SUBSCRIPT"));
+            }
+            subscripts.push(n as usize);
+            if !self.accept(",") {
+                self.expect(")")?;
+                return Ok(subscripts);
+            }
+        }
+    }
+
+    /// An array element's index into its values, dimensioning it if needed.
+    fn element(&mut self, name: &str, subscripts: &[usize]) -> Run<usize> {
+        if !self.arrays.contains_key(name) {
+            self.dim(name.to_string(), vec![DEFAULT_DIM; subscripts.len()]);
+        }
+        let array = &self.arrays[name];
+        if array.dims.len() != subscripts.len() {
+            return Err(Halt::Error("BAD SUBSCRIPT"));
+        }
+        let mut index = 0;
+        for (&sub, &dim) in subscripts.iter().zip(&array.dims) {
+            if sub > dim {
+                return Err(Halt::Error("BAD SUBSCRIPT"));
+            }
+            index = index * (dim + 1) + sub;
+        }
+        Ok(index)
+    }
+
+    /// A variable or array element to assign to.
+    fn target(&mut self) -> Run<(String, Option<Vec<usize>>)> {
+        let name = match self.next() {
+            Some(Token::Name(name)) => var_key(&name),
+            _ => return Err(SYNTAX)
+        };
+        let subscripts = if self.accept("(") { Some(self.subscripts()?) } else { None };
+        Ok((name, subscripts))
+    }
+
+    fn assign(&mut self, (name, subscripts): (String, Option<Vec<usize>>), value: Value) -> Run<()> {
+        if name.ends_with('$') != matches!(value, Value::Str(_)) {
+            return Err(TYPE_MISMATCH);
+        }
+        match subscripts {
+            Some(subscripts) => {
+                let index = self.element(&name, &subscripts)?;
+                self.arrays.get_mut(&name).unwrap().values[index] = value;
+            }
+            None => {
+                self.vars.insert(name, value);
+            }
+        }
+        Ok(())
+    }
+
+    fn assignment(&mut self) -> Run<()> {
+        let target = self.target()?;
+        self.expect("=")?;
+        let value = self.expr()?;
+        self.assign(target, value)
+    }
+
+    fn num(&mut self) -> Run<f64> {
+        match self.expr()? {
+            Value::Num(n) => Ok(n),
+            Value::Str(_) => Err(TYPE_MISMATCH)
+        }
+    }
+
+    fn string(&mut self) -> Run<String> {
+        match self.expr()? {
+            Value::Str(s) => Ok(s),
+            Value::Num(_) => Err(TYPE_MISMATCH)
+        }
+    }
+
+    fn int(n: f64) -> Run<i32> {
+        if (-32768.0..32768.0).contains(&n) { Ok(n.floor() as i32) } else { Err(ILLEGAL_QUANTITY) }
+    }
+
+    fn expr(&mut self) -> Run<Value> {
+        let mut value = self.and_expr()?;
+        while self.accept_keyword(Or) {
+            let right = self.and_expr()?;
+            value = self.logic(value, right, |a, b| a | b)?;
+        }
+        Ok(value)
+    }
+
+    fn and_expr(&mut self) -> Run<Value> {
+        let mut value = self.not_expr()?;
+        while self.accept_keyword(And) {
+            let right = self.not_expr()?;
+            value = self.logic(value, right, |a, b| a & b)?;
+        }
+        Ok(value)
+    }
+
+    fn logic(&self, left: Value, right: Value, op: fn(i32, i32) -> i32) -> Run<Value> {
+        match (left, right) {
+            (Value::Num(a), Value::Num(b)) => Ok(Value::Num(op(Self::int(a)?, Self::int(b)?) as f64)),
+            _ => Err(TYPE_MISMATCH)
+        }
+    }
+
+    fn not_expr(&mut self) -> Run<Value> {
+        if self.accept_keyword(Not) {
+            let n = match self.not_expr()? {
+                Value::Num(n) => n,
+                Value::Str(_) => return Err(TYPE_MISMATCH)
+            };
+            return Ok(Value::Num(!Self::int(n)? as f64));
+        }
+        self.relation()
+    }
+
+    fn relation(&mut self) -> Run<Value> {
+        let mut value = self.additive()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op(op)) if ["=", "<>", "><", "<", ">", "<=", "=<", ">=", "=>"].contains(op) => *op,
+                _ => return Ok(value)
+            };
+            self.pos.token += 1;
+            let right = self.additive()?;
+            let ordering = match (&value, &right) {
+                (Value::Num(a), Value::Num(b)) => a.partial_cmp(b).ok_or(ILLEGAL_QUANTITY)?,
+                (Value::Str(a), Value::Str(b)) => a.cmp(b),
+                _ => return Err(TYPE_MISMATCH)
+            };
+            value = truth(match op {
+                "=" => ordering.is_eq(),
+                "<>" | "><" => ordering.is_ne(),
+                "<" => ordering.is_lt(),
+                ">" => ordering.is_gt(),
+                "<=" | "=<" => ordering.is_le(),
+                _ => ordering.is_ge()
+            });
+        }
+    }
+
+    fn additive(&mut self) -> Run<Value> {
+        let mut value = self.term()?;
+        loop {
+            if self.accept("+") {
+                value = match (value, self.term()?) {
+                    (Value::Num(a), Value::Num(b)) => Value::Num(a + b),
+                    (Value::Str(a), Value::Str(b)) => Value::Str(a + &b),
+                    _ => return Err(TYPE_MISMATCH)
+                };
+            } else if self.accept("-") {
+                value = match (value, self.term()?) {
+                    (Value::Num(a), Value::Num(b)) => Value::Num(a - b),
+                    _ => return Err(TYPE_MISMATCH)
+                };
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Run<Value> {
+        let mut value = self.unary()?;
+        loop {
+            let multiply = if self.accept("*") {
+                true
+            } else if self.accept("/") {
+                false
+            } else {
+                return Ok(value);
+            };
+            value = match (value, self.unary()?) {
+                (Value::Num(a), Value::Num(b)) if multiply => Value::Num(a * b),
+                (Value::Num(_), Value::Num(0.0)) => return Err(Halt::Error("DIVISION BY ZERO")),
+                (Value::Num(a), Value::Num(b)) => Value::Num(a / b),
+                _ => return Err(TYPE_MISMATCH)
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Run<Value> {
+        if self.accept("-") {
+            return match self.unary()? {
+                Value::Num(n) => Ok(Value::Num(-n)),
+                Value::Str(_) => Err(TYPE_MISMATCH)
+            };
+        }
+        if self.accept("+") {
+            return self.unary();
+        }
+        let mut value = self.atom()?;
+        while self.accept("^") {
+            value = match (value, self.atom()?) {
+                (Value::Num(a), Value::Num(b)) => Value::Num(a.powf(b)),
+                _ => return Err(TYPE_MISMATCH)
+            };
+        }
+        Ok(value)
+    }
+
+    fn atom(&mut self) -> Run<Value> {
+        match self.next().ok_or(SYNTAX)? {
+            Token::Num(n) => Ok(Value::Num(n)),
+            Token::Str(s) => Ok(Value::Str(s)),
+            Token::Name(name) => {
+                let name = var_key(&name);
+                if self.accept("(") {
+                    let subscripts = self.subscripts()?;
+                    let index = self.element(&name, &subscripts)?;
+                    return Ok(self.arrays[&name].values[index].clone());
+                }
+                let default = if name.ends_with('$') { Value::Str(String::new()) } else { Value::Num(0.0) };
+                Ok(self.vars.get(&name).cloned().unwrap_or(default))
+            }
+            Token::Op("(") => {
+                let value = self.expr()?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            Token::Kw(keyword) => {
+                self.expect("(")?;
+                let value = self.function(keyword)?;
+                self.expect(")")?;
+                Ok(value)
+            }
+            _ => Err(SYNTAX)
+        }
+    }
+
+    /// A function's arguments and result, between its brackets.
+    fn function(&mut self, keyword: Keyword) -> Run<Value> {
+        let count = |n: f64| if (0.0..256.0).contains(&n) { Ok(n as usize) } else { Err(ILLEGAL_QUANTITY) };
+        Ok(match keyword {
+            Left | Right => {
+                let s = self.string()?;
+                self.expect(",")?;
+                let len = s.chars().count();
+                let n = count(self.num()?)?.min(len);
+                let skip = if keyword == Left { 0 } else { len - n };
+                Value::Str(s.chars().skip(skip).take(n).collect())
+            }
+            Mid => {
+                let s = self.string()?;
+                self.expect(",")?;
+                let start = count(self.num()?)?;
+                if start == 0 {
+                    return Err(ILLEGAL_QUANTITY);
+                }
+                let n = if self.accept(",") { count(self.num()?)? } else { 255 };
+                Value::Str(s.chars().skip(start - 1).take(n).collect())
+            }
+            Len => Value::Num(self.string()?.chars().count() as f64),
+            Asc => match self.string()?.chars().next() {
+                Some(c) => Value::Num(c as u32 as f64),
+                None => return Err(ILLEGAL_QUANTITY)
+            },
+            Val => Value::Num(parse_number(&self.string()?).unwrap_or(0.0)),
+            Chr => Value::Str((count(self.num()?)? as u8 as char).to_string()),
+            Str => Value::Str(str_number(self.num()?)),
+            Int => Value::Num(self.num()?.floor()),
+            Abs => Value::Num(self.num()?.abs()),
+            Sgn => {
+                let n = self.num()?;
+                Value::Num(if n > 0.0 { 1.0 } else if n < 0.0 { -1.0 } else { 0.0 })
+            }
+            Sqr => {
+                let n = self.num()?;
+                if n < 0.0 {
+                    return Err(ILLEGAL_QUANTITY);
+                }
+                Value::Num(n.sqrt())
+            }
+            Rnd => {
+                self.num()?;
+                self.seed = self.seed.wrapping_mul(1103515245).wrapping_add(12345);
+                Value::Num((self.seed >> 8) as f64 / (1 << 24) as f64)
+            }
+            Peek => {
+                self.num()?;
+                Value::Num(0.0)
+            }
+            _ => return Err(SYNTAX)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Types its lines at INPUT and keeps what's printed.
+    struct Typist {
+        lines: Vec<&'static str>,
+        printed: String
+    }
+
+    impl Terminal for Typist {
+        fn print(&mut self, text: &str) -> io::Result<()> {
+            self.printed += text;
+            Ok(())
+        }
+
+        fn read_line(&mut self) -> io::Result<Option<String>> {
+            Ok(if self.lines.is_empty() { None } else { Some(self.lines.remove(0).to_string()) })
+        }
+    }
+
+    fn printed(listing: &str, lines: &[&'static str]) -> String {
+        let mut typist = Typist { lines: lines.to_vec(), printed: String::new() };
+        run(listing, &mut typist).unwrap();
+        typist.printed
+    }
+
+    #[test]
+    fn string_functions() {
+        let listing = r#"
+10 A$="HELLO"
+20 PRINT LEFT$(A$,2);"/";RIGHT$(A$,3);"/";MID$(A$,2,3);"/";MID$(A$,4)
+30 PRINT LEN(A$);ASC(A$);CHR$(33);STR$(7);VAL("12")+1
+"#;
+        assert_eq!(printed(listing, &[]), "HE/LLO/ELL/LO\n 5  72 ! 7 13 \n");
+    }
+
+    #[test]
+    fn string_functions_count_characters() {
+        let listing = r#"
+10 A$="ÉÉX"
+20 PRINT LEFT$(A$,1);"/";RIGHT$(A$,2);"/";MID$(A$,2,1);"/";LEN(A$)
+"#;
+        assert_eq!(printed(listing, &[]), "É/ÉX/É/ 3 \n");
+    }
+
+    #[test]
+    fn loops_subroutines_and_input() {
+        let listing = r#"
+10 INPUT "NAME";N$
+20 FOR I=1 TO 3:GOSUB 100:NEXT
+30 IF N$="BOB" THEN PRINT "HI BOB":END
+40 PRINT "WHO?"
+100 PRINT I;:RETURN
+"#;
+        assert_eq!(printed(listing, &["BOB"]), "NAME?  1  2  3 HI BOB\n");
+    }
+
+    #[test]
+    fn reads_data_in_order() {
+        let listing = "10 READ A$,B:PRINT A$;B\n20 DATA \"X, Y\",2\n";
+        assert_eq!(printed(listing, &[]), "X, Y 2 \n");
+    }
+
+    #[test]
+    fn errors_stop_the_program() {
+        assert_eq!(printed("10 PRINT LEFT$(\"A\",-1)\n20 PRINT 2", &[]), "?ILLEGAL QUANTITY ERROR IN 10\n");
+        assert_eq!(printed("10 PRINT (\n", &[]), "?SYNTAX ERROR IN 10\n");
+        assert_eq!(printed("10 GOSUB 10\n", &[]), "?OUT OF MEMORY ERROR IN 10\n");
+        assert_eq!(printed("10 FOR I=1 TO 2\n20 GOSUB 10\n", &[]), "?OUT OF MEMORY ERROR IN 10\n");
+    }
+}
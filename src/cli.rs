@@ -0,0 +1,209 @@
+//! The command line front end, which the `lampton` binary runs: plays on
+//! stdin and stdout, or runs one of the tools on the current world.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+
+use super::{basic, diff, editor, export, ffi, http, import, inform, json, play, server, teletype, trace, tui, wrap, zcode, Stdio, World, FIXES, LISTING, STOCK};
+
+fn fail(msg: &str) -> ! {
+    eprintln!("lampton: {}", msg);
+    process::exit(2);
+}
+
+/// Each fix's name, and what happens without it, a line each.
+fn fixes() -> String {
+    let mut list = String::new();
+    for (_, name, original) in FIXES.iter() {
+        list += &format!("\n  {:8} without it, {}", name, original);
+    }
+    list
+}
+
+/// Reads the arguments, and does what they say.
+pub fn run() -> io::Result<()> {
+    let mut confirm = true;
+    let mut export = false;
+    let mut inform = false;
+    let mut header = false;
+    let mut basic = None;
+    let mut original = 0;
+    let mut trace = false;
+    let mut json = false;
+    let mut serve = None;
+    let mut shared = false;
+    let mut web = None;
+    let mut full_screen = false;
+    let mut script = None;
+    let mut zcode = None;
+    let mut width = None;
+    let mut teletype = None;
+    let mut forty = false;
+    let mut modern = false;
+    let mut world = &STOCK;
+    let mut listing = LISTING.to_string();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // scripts can't answer ARE YOU SURE?
+            "-y" | "--yes" => confirm = false,
+            "--world" => {
+                let path = args.next().unwrap_or_else(|| fail("--world needs a BASIC listing"));
+                listing = fs::read_to_string(&path)
+                    .unwrap_or_else(|err| fail(&format!("{}: {}", path, err)));
+                world = import::import(&listing)
+                    .unwrap_or_else(|err| fail(&format!("{}: {}", path, err)));
+            }
+            "--export" => export = true,
+            "--inform" => inform = true,
+            "--header" => header = true,
+            "--faithful" => original = !0,
+            "--trace" => trace = true,
+            "--json" => json = true,
+            "--shared" => shared = true,
+            "--tui" => full_screen = true,
+            "--width" => {
+                let columns = args.next().unwrap_or_else(|| fail("--width needs a number of columns, or terminal"));
+                width = Some(match columns.as_str() {
+                    // 80 when there's no terminal to ask
+                    "terminal" => tui::size().map_or(80, |(_, columns)| columns),
+                    _ => columns.parse().ok().filter(|&width| width > 0)
+                        .unwrap_or_else(|| fail(&format!("no width {}", columns)))
+                });
+            }
+            "--teletype" => {
+                let baud = args.next().unwrap_or_else(|| fail("--teletype needs a baud rate"));
+                teletype = Some(baud.parse().ok().filter(|&baud| baud > 0)
+                    .unwrap_or_else(|| fail(&format!("no baud rate {}", baud))));
+            }
+            "--forty" => forty = true,
+            "--modern" => modern = true,
+            "--http" => {
+                let port = args.next().unwrap_or_else(|| fail("--http needs a port"));
+                web = Some(port.parse().unwrap_or_else(|_| fail(&format!("no port {}", port))));
+            }
+            "--serve" => {
+                let addr = args.next().unwrap_or_else(|| fail("--serve needs a port or address"));
+                // a bare port is only open to this machine
+                serve = Some(if addr.parse::<u16>().is_ok() { format!("127.0.0.1:{}", addr) } else { addr });
+            }
+            "--original" => {
+                let names = args.next().unwrap_or_else(|| fail("--original needs the name of a fix"));
+                if names == "help" {
+                    println!("--original NAME[,NAME] switches fixes back to the book's behaviour:{}", fixes());
+                    return Ok(());
+                }
+                for name in names.split(',') {
+                    match FIXES.iter().find(|&&(_, fix_name, _)| fix_name == name) {
+                        Some(&(fix, _, _)) => original |= fix.bit(),
+                        None => fail(&format!("unknown fix {}; the fixes are:{}", name, fixes()))
+                    }
+                }
+            }
+            "--basic" => {
+                let path = args.next().unwrap_or_else(|| fail("--basic needs a BASIC listing"));
+                basic = Some(fs::read_to_string(&path)
+                    .unwrap_or_else(|err| fail(&format!("{}: {}", path, err))));
+            }
+            "--diff" => {
+                let path = args.next().unwrap_or_else(|| fail("--diff needs a script of commands"));
+                script = Some(fs::read_to_string(&path)
+                    .unwrap_or_else(|err| fail(&format!("{}: {}", path, err))));
+            }
+            "--zcode" => {
+                zcode = Some(args.next().unwrap_or_else(|| fail("--zcode needs a file to write")));
+            }
+            _ => fail(&format!("unknown option {}", arg))
+        }
+    }
+
+    // how the game looks played here, which the tools and other front ends leave alone
+    let laid_out = [(width.is_some(), "--width"), (modern, "--modern")];
+    let shown = [(teletype.is_some(), "--teletype"), (forty, "--forty"), (full_screen, "--tui")];
+    let tools = [
+        (export, "--export"), (inform, "--inform"), (header, "--header"), (zcode.is_some(), "--zcode"),
+        (script.is_some(), "--diff"), (serve.is_some(), "--serve"), (web.is_some(), "--http"),
+        (json, "--json"), (basic.is_some() && !trace, "--basic"),
+    ];
+    let first = |options: &[(bool, &'static str)]| options.iter().find(|&&(on, _)| on).map(|&(_, name)| name);
+    if let Some(tool) = first(&tools) {
+        if let Some(option) = first(&laid_out).or_else(|| first(&shown)) {
+            fail(&format!("{} isn't for {}", option, tool));
+        }
+    }
+    if let Some(option) = first(&shown).filter(|_| trace) {
+        fail(&format!("{} isn't for --trace", option));
+    }
+    if teletype.is_some() && full_screen {
+        fail("--teletype isn't for --tui");
+    }
+    if forty && modern {
+        fail("--forty is in the book's capitals, so not --modern");
+    }
+    if forty && width.is_some() {
+        fail("--forty is 40 columns, so not --width");
+    }
+    let width = width.unwrap_or(wrap::BOOK_WIDTH);
+
+    if original != 0 {
+        world = Box::leak(Box::new(World { original, ..*world }));
+    }
+    if export {
+        print!("{}", export::export(world));
+        return Ok(());
+    }
+    if inform {
+        print!("{}", inform::export(world));
+        return Ok(());
+    }
+    if header {
+        print!("{}", ffi::HEADER);
+        return Ok(());
+    }
+    if let Some(path) = zcode {
+        return fs::write(&path, zcode::compile(world));
+    }
+    if let Some(script) = script {
+        // against the listing the world came from, unless given another
+        let listing = basic.unwrap_or(listing);
+        if diff::diff(&script, &listing, world)? {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(addr) = serve {
+        return server::serve(&addr, world, confirm, shared);
+    }
+    if shared {
+        fail("--shared is for --serve");
+    }
+    if let Some(port) = web {
+        return http::serve(port, world, confirm);
+    }
+    if json {
+        return json::play(&mut Stdio, world, confirm);
+    }
+    if trace {
+        let listing = basic.unwrap_or(listing);
+        let mut term = trace::Traced::new(editor::Editor::new(Stdio), listing, width)?;
+        return play(&mut term, world.as_printed(), confirm, width, modern);
+    }
+    if let Some(listing) = basic {
+        return basic::run(&listing, &mut editor::Editor::new(Stdio));
+    }
+    if let Some(baud) = teletype {
+        let width = if forty { wrap::BOOK_WIDTH } else { width };
+        let mut term = teletype::Teletype::new(editor::Editor::new(Stdio), baud, forty);
+        return play(&mut term, world, confirm, width, modern);
+    }
+    if forty {
+        fail("--forty is for --teletype");
+    }
+    if full_screen {
+        return tui::play(&mut editor::Editor::new(Stdio), world, confirm, width, modern);
+    }
+
+    play(&mut editor::Editor::new(Stdio), world, confirm, width, modern)
+}
@@ -0,0 +1,129 @@
+//! Plays the same commands through the port and through a BASIC listing,
+//! and shows where their replies part ways. Lines only BASIC printed are
+//! marked with -, lines only the port printed with +.
+
+use std::io;
+use std::slice;
+
+use super::wrap::BOOK_WIDTH;
+use super::{basic, play, Terminal, World};
+
+/// Feeds a game its commands from a script and keeps what it prints.
+struct Script<'a> {
+    commands: slice::Iter<'a, &'a str>,
+    /// What was printed before the first command, then after each one.
+    replies: Vec<String>
+}
+
+impl Terminal for Script<'_> {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.replies.last_mut().unwrap().push_str(text);
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let command = self.commands.next().map(|command| command.to_string());
+        if command.is_some() {
+            self.replies.push(String::new());
+        }
+        Ok(command)
+    }
+}
+
+fn replay(commands: &[&str], game: impl FnOnce(&mut Script) -> io::Result<()>) -> io::Result<Vec<String>> {
+    let mut script = Script { commands: commands.iter(), replies: vec![String::new()] };
+    game(&mut script)?;
+    Ok(script.replies)
+}
+
+/// Prints the differences and returns whether there were any.
+pub fn diff(script: &str, listing: &str, world: &'static World) -> io::Result<bool> {
+    let commands: Vec<&str> = script.lines().collect();
+    let original = replay(&commands, |term| basic::run(listing, term))?;
+    let port = replay(&commands, |term| play(term, world.as_printed(), true, BOOK_WIDTH, false))?;
+
+    let mut differ = 0;
+    for i in 0..original.len().max(port.len()) {
+        let lines = |replies: &[String]| -> Vec<String> {
+            replies.get(i).map_or(Vec::new(), |reply| {
+                reply.lines().map(|line| line.trim_end().to_string()).collect()
+            })
+        };
+        let changes = changes(&lines(&original), &lines(&port));
+        if changes.is_empty() {
+            continue;
+        }
+        differ += 1;
+        match i {
+            0 => println!("> (start)"),
+            _ => println!("> {}", commands[i - 1])
+        }
+        for change in changes {
+            println!("{}", change);
+        }
+        println!();
+    }
+    println!("{} of {} replies differ.", differ, commands.len() + 1);
+    Ok(differ > 0)
+}
+
+/// The lines to remove from `a` and add to make `b`, from their longest common subsequence.
+fn changes(a: &[String], b: &[String]) -> Vec<String> {
+    let mut common = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && common[i + 1][j] >= common[i][j + 1]) {
+            changes.push(format!("- {}", a[i]));
+            i += 1;
+        } else {
+            changes.push(format!("+ {}", b[j]));
+            j += 1;
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LISTING, STOCK};
+
+    fn lines(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn changes_keep_the_common_lines() {
+        let (a, b) = (lines("A B C D"), lines("A C E D"));
+        assert_eq!(changes(&a, &b), ["- B", "+ E"]);
+        assert_eq!(changes(&a, &a), Vec::<String>::new());
+        assert_eq!(changes(&[], &lines("X")), ["+ X"]);
+        assert_eq!(changes(&lines("X"), &[]), ["- X"]);
+    }
+
+    #[test]
+    fn the_faithful_port_replies_as_its_basic_export_does() {
+        let commands = [
+            "LOOK", "OPEN BOX", "OPEN BOX", "GET BOTTLE", "E", "OPEN CABINET", "READ DICTIONARY",
+            "EXAMINE CABINET", "WEAR", "FLY", "W", "N", "GET LADDER", "INVENTORY", "QUIT", "Y",
+        ];
+        let faithful: &'static World = Box::leak(Box::new(World { original: !0, ..STOCK }));
+        let basic = replay(&commands, |term| basic::run(LISTING, term)).unwrap();
+        let port = replay(&commands, |term| play(term, faithful, true, BOOK_WIDTH, false)).unwrap();
+        assert_eq!(port, basic);
+    }
+}
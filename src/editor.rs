@@ -0,0 +1,264 @@
+//! Line editing for the prompt when typing at a terminal: left and right,
+//! home and end, up and down through earlier commands, and tab to finish
+//! a verb or the name of something in scope.
+//!
+//! The terminal goes into raw mode with stty for each line, and back
+//! afterwards. Input that isn't a terminal is read a line at a time as usual.
+
+use std::io::{self, prelude::*, IsTerminal};
+
+use super::tui::stty;
+use super::Terminal;
+
+const CTRL_A: u8 = 1;
+const CTRL_C: u8 = 3;
+const CTRL_D: u8 = 4;
+const CTRL_E: u8 = 5;
+const BACKSPACE: u8 = 8;
+const TAB: u8 = 9;
+const CTRL_U: u8 = 21;
+const ESC: u8 = 27;
+const DELETE: u8 = 127;
+
+/// Wraps another terminal, editing the lines read from it.
+pub struct Editor<T: Terminal> {
+    inner: T,
+    history: Vec<String>,
+    /// What's worth typing at the prompt being read.
+    commands: Vec<String>,
+    /// The last line printed, up to the cursor, to redraw after listing completions.
+    prompt: String
+}
+
+/// Puts the terminal back as it was, however reading ends.
+struct Cooked(String);
+
+impl Drop for Cooked {
+    fn drop(&mut self) {
+        stty(&[self.0.trim()]);
+    }
+}
+
+/// The line being typed.
+struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+    /// Where the cursor is on screen, from the start of the line.
+    shown: usize
+}
+
+impl<T: Terminal> Editor<T> {
+    pub fn new(inner: T) -> Self {
+        Editor { inner, history: Vec::new(), commands: Vec::new(), prompt: String::new() }
+    }
+
+    fn edit(&mut self) -> io::Result<Option<String>> {
+        let mut line = Line { chars: Vec::new(), cursor: 0, shown: 0 };
+        // how far back up the history, where history.len() is the line being typed
+        let mut recalled = self.history.len();
+        let mut typed = Vec::new();
+        let stdin = io::stdin();
+        let mut bytes = stdin.lock().bytes();
+        let mut next = || bytes.next().transpose();
+        loop {
+            let byte = match next()? {
+                Some(byte) => byte,
+                None => return Ok(None)
+            };
+            match byte {
+                b'\r' | b'\n' => break,
+                CTRL_C => return Ok(None),
+                CTRL_D if line.chars.is_empty() => return Ok(None),
+                CTRL_A => line.cursor = 0,
+                CTRL_E => line.cursor = line.chars.len(),
+                CTRL_U => {
+                    line.chars.drain(..line.cursor);
+                    line.cursor = 0;
+                }
+                BACKSPACE | DELETE if line.cursor > 0 => {
+                    line.cursor -= 1;
+                    line.chars.remove(line.cursor);
+                }
+                TAB => self.complete(&mut line)?,
+                ESC => {
+                    // arrows are ESC [ A, or ESC O A; delete is ESC [ 3 ~
+                    let key = match (next()?, next()?) {
+                        (Some(b'['), Some(b'3')) => { next()?; b'~' }
+                        (Some(b'['), Some(key)) | (Some(b'O'), Some(key)) => key,
+                        _ => continue
+                    };
+                    match key {
+                        b'A' | b'B' => {
+                            if recalled == self.history.len() {
+                                typed = line.chars.clone();
+                            }
+                            recalled = match key {
+                                b'A' => recalled.saturating_sub(1),
+                                _ => (recalled + 1).min(self.history.len())
+                            };
+                            line.chars = match self.history.get(recalled) {
+                                Some(command) => command.chars().collect(),
+                                None => typed.clone()
+                            };
+                            line.cursor = line.chars.len();
+                        }
+                        b'C' => line.cursor = (line.cursor + 1).min(line.chars.len()),
+                        b'D' => line.cursor = line.cursor.saturating_sub(1),
+                        b'H' => line.cursor = 0,
+                        b'F' => line.cursor = line.chars.len(),
+                        b'~' if line.cursor < line.chars.len() => { line.chars.remove(line.cursor); }
+                        _ => {}
+                    }
+                }
+                b' '..=b'~' => {
+                    line.chars.insert(line.cursor, byte as char);
+                    line.cursor += 1;
+                }
+                _ => {}
+            }
+            self.redraw(&mut line)?;
+        }
+        self.inner.print("\n")?;
+        let command: String = line.chars.iter().collect();
+        if !command.trim().is_empty() && self.history.last() != Some(&command) {
+            self.history.push(command.clone());
+        }
+        Ok(Some(command))
+    }
+
+    /// Finishes the word before the cursor, as far as all the commands
+    /// that fit what's typed agree, or lists them if they don't.
+    fn complete(&mut self, line: &mut Line) -> io::Result<()> {
+        let before: String = line.chars[..line.cursor].iter().collect();
+        let mut words: Vec<&str> = before.split(' ').collect();
+        let partial = words.pop().unwrap_or("").to_ascii_uppercase();
+        // earlier words count as typed if their first three letters are, as in the parser
+        let same = |typed: &str, word: &str| {
+            let typed = typed.to_ascii_uppercase();
+            word.starts_with(&typed[..typed.len().min(3)]) && typed.len() <= word.len().max(3)
+        };
+        let mut fits: Vec<&str> = self.commands.iter()
+            .map(|command| command.split(' ').collect::<Vec<_>>())
+            .filter(|command| command.len() > words.len())
+            .filter(|command| words.iter().zip(command).all(|(typed, word)| same(typed, word)))
+            .map(|command| command[words.len()])
+            .filter(|word| word.starts_with(&partial))
+            .collect();
+        fits.sort_unstable();
+        fits.dedup();
+        let common = match fits.first() {
+            Some(first) => fits.iter().fold(first.len(), |common, fit| {
+                first.bytes().zip(fit.bytes()).take(common).take_while(|(a, b)| a == b).count()
+            }),
+            None => return Ok(())
+        };
+        let mut finish: String = fits[0][partial.len()..common].to_string();
+        if fits.len() == 1 {
+            finish.push(' ');
+        } else if finish.is_empty() {
+            // nothing more in common, so show what there is
+            let list = format!("\n{}\n{}", fits.join("  "), self.prompt);
+            self.inner.print(&list)?;
+            // and the line's drawn again after the prompt
+            line.shown = 0;
+        }
+        // typed in lower case, it carries on in lower case
+        if before.chars().any(|c| c.is_ascii_lowercase()) {
+            finish.make_ascii_lowercase();
+        }
+        for c in finish.chars() {
+            line.chars.insert(line.cursor, c);
+            line.cursor += 1;
+        }
+        Ok(())
+    }
+
+    /// Writes the line over what was there, and puts the cursor where it goes.
+    fn redraw(&mut self, line: &mut Line) -> io::Result<()> {
+        let mut text = String::new();
+        if line.shown > 0 {
+            text += &format!("\x1b[{}D", line.shown);
+        }
+        text.extend(&line.chars);
+        text += "\x1b[K";
+        if line.chars.len() > line.cursor {
+            text += &format!("\x1b[{}D", line.chars.len() - line.cursor);
+        }
+        line.shown = line.cursor;
+        self.inner.print(&text)
+    }
+}
+
+impl<T: Terminal> Terminal for Editor<T> {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        match text.rsplit_once('\n') {
+            Some((_, last)) => self.prompt = last.to_string(),
+            None => self.prompt += text
+        }
+        self.inner.print(text)
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        if !io::stdin().is_terminal() {
+            return self.inner.read_line();
+        }
+        let _cooked = match stty(&["-g"]) {
+            Some(settings) => Cooked(settings),
+            None => return self.inner.read_line()
+        };
+        if stty(&["-icanon", "-echo", "-isig", "min", "1"]).is_none() {
+            return self.inner.read_line();
+        }
+        self.edit()
+    }
+
+    fn trace(&mut self, line: u32) {
+        self.inner.trace(line);
+    }
+
+    fn offer(&mut self, commands: &[String]) {
+        self.commands = commands.to_vec();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::Typist;
+
+    fn completed(editor: &mut Editor<Typist>, typed: &str) -> String {
+        let mut line = Line { chars: typed.chars().collect(), cursor: typed.chars().count(), shown: 0 };
+        editor.complete(&mut line).unwrap();
+        line.chars.iter().collect()
+    }
+
+    #[test]
+    fn tab_finishes_what_the_commands_agree_on() {
+        let mut editor = Editor::new(Typist::new(&[]));
+        editor.offer(&["NORTH", "OPEN BOX", "OPEN BOTTLE", "GET BOX"].map(String::from));
+        assert_eq!(completed(&mut editor, "OP"), "OPEN ");
+        assert_eq!(completed(&mut editor, "n"), "north ");
+        assert_eq!(completed(&mut editor, "GET B"), "GET BOX ");
+        assert_eq!(completed(&mut editor, "OPE BOT"), "OPE BOTTLE ");
+        assert_eq!(completed(&mut editor, "WAVE"), "WAVE");
+        assert_eq!(editor.inner.printed, "");
+    }
+
+    #[test]
+    fn tab_lists_the_commands_that_disagree() {
+        let mut editor = Editor::new(Typist::new(&[]));
+        editor.print("\nWHAT NOW? ").unwrap();
+        editor.offer(&["OPEN BOX", "OPEN BOTTLE"].map(String::from));
+        assert_eq!(completed(&mut editor, "OPEN BO"), "OPEN BO");
+        assert!(editor.inner.printed.ends_with("\nBOTTLE  BOX\nWHAT NOW? "));
+    }
+
+    #[test]
+    fn the_line_is_redrawn_with_the_cursor_where_it_goes() {
+        let mut editor = Editor::new(Typist::new(&[]));
+        let mut line = Line { chars: "GET".chars().collect(), cursor: 1, shown: 2 };
+        editor.redraw(&mut line).unwrap();
+        assert_eq!(editor.inner.printed, "\x1b[2DGET\x1b[K\x1b[2D");
+        assert_eq!(line.shown, 1);
+    }
+}
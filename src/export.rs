@@ -0,0 +1,363 @@
+//! Writes a world back out as a BASIC listing, laid out like the book's:
+//! parser at 100, movement at 2000, verbs at 2500-4900, DATA at 25000 on.
+//!
+//! It sticks to what every 8-bit Microsoft BASIC understands: two-letter
+//! variable names, lines under 80 columns, and no LINE INPUT. Our additions
+//! (scoring, daemons, NPCs, dying, saving) stay behind; the puzzles come along
+//! only for worlds that have them, same as in the port.
+
+use std::collections::BTreeMap;
+
+use super::wrap::as_printed;
+use super::{
+    Fix, World, BOAT_OBJ, BOAT_ROOM, BOTTLE, CASTLE_GATE, FOREST_EDGE, GARAGE, GLOVES, GUARD,
+    GUARDED, INVENTORY, LADDER, MAX_INVENTORY, NARROW_HALL, NORTH_BANK, OPEN_FIELD, RUBY,
+    SALT, SOUTH_BANK, START_ROOM, SWORD, TREE_BRANCH, TREE_TOP
+};
+
+/// Line 100 onwards, for any world.
+const GENERIC: &str = r#"
+10 REM HOW TO CREATE ADVENTURE GAMES
+15 REM BY CHRISTOPHER LAMPTON (1986)
+20 NR={NR}:NO={NO}:MI={MI}
+30 GOSUB 25000
+40 R={START}:GOSUB 700
+100 A$="":PRINT:INPUT "WHAT NOW";A$
+110 NW=0:W$="":A$=A$+" "
+120 FOR I=1 TO LEN(A$):C$=MID$(A$,I,1)
+130 IF C$<>" " THEN W$=W$+C$:GOTO 160
+140 IF W$<>"" THEN NW=NW+1:IF NW<4 THEN WD$(NW)=LEFT$(W$,3)
+150 W$=""
+160 NEXT
+170 IF NW=0 THEN 100
+180 V$=WD$(1):N$="":IF NW>1 THEN N$=WD$(2)
+190 D$=V$:IF V$="GO" AND NW>1 THEN D$=N$
+200 D=0
+210 IF N$="SHA" THEN N$="SAL"
+220 IF N$="FOR" THEN N$="BOT"
+230 IF D$="N" OR D$="NOR" THEN D=1
+240 IF D$="S" OR D$="SOU" THEN D=2
+250 IF D$="E" OR D$="EAS" THEN D=3
+260 IF D$="W" OR D$="WES" THEN D=4
+270 IF D$="U" OR D$="UP" THEN D=5
+280 IF D$="D" OR D$="DOW" THEN D=6
+290 IF D$="BOA" THEN D=7
+295 IF D>0 THEN 2000
+300 IF NW>2 THEN 390
+305 IF NW=1 AND (V$="Q" OR V$="QUI") THEN 3400
+310 IF NW=1 AND (V$="I" OR V$="INV") THEN 2700
+315 IF NW=1 AND (V$="L" OR V$="LOO") THEN GOSUB 700:GOTO 100
+320 IF NW=1 AND V$="GO" THEN PRINT "GO WHERE?":GOTO 100
+325 IF V$="EXA" OR V$="LOO" THEN 2900
+330 IF V$="GET" OR V$="TAK" THEN 2500
+335 IF V$="DRO" THEN 2600
+390 PRINT "I DON'T KNOW HOW TO DO THAT.":GOTO 100
+500 PRINT "YOU CAN GO:";
+510 IF EX(R,1)>0 THEN PRINT " NORTH";
+520 IF EX(R,2)>0 THEN PRINT " SOUTH";
+530 IF EX(R,3)>0 THEN PRINT " EAST";
+540 IF EX(R,4)>0 THEN PRINT " WEST";
+550 IF EX(R,6)>0 THEN PRINT " DOWN";
+560 IF EX(R,5)>0 THEN PRINT " UP";
+570 RETURN
+600 PRINT:PRINT "YOU CAN SEE:":F=0
+610 FOR K=1 TO NO:IF (OL(K) AND 127)=R THEN PRINT "    ";OB$(K):F=1
+620 NEXT
+630 IF F=0 THEN PRINT "    THERE IS NOTHING OF INTEREST HERE."
+640 RETURN
+700 PRINT:PRINT "YOU ARE ";R$(R)
+710 GOSUB 500:GOSUB 600:RETURN
+1000 FOR I=1 TO NO:IF OT$(I)=N$ THEN RETURN
+1010 NEXT:I=0:RETURN
+1100 H=0:IF I=0 THEN RETURN
+1110 IF OL(I)=-1 OR (OL(I) AND 127)=R THEN H=1
+1120 RETURN
+1200 C=0:FOR K=1 TO NO:IF OL(K)=-1 THEN C=C+1
+1210 NEXT:RETURN
+2000 IF D=7 THEN 2100
+2010 DE=EX(R,D)
+2020 IF DE>0 AND DE<=NR THEN R=DE:GOSUB 700:GOTO 100
+2040 PRINT "YOU CAN'T GO THERE!":GOTO 100
+2100 DE=0
+2110 GOTO 2020
+2500 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO GET?":GOTO 100
+2520 GOSUB 1000:IF I=0 THEN PRINT "YOU CAN'T GET THAT!":GOTO 100
+2530 IF OL(I)=-1 THEN PRINT "YOU ALREADY HAVE IT!":GOTO 100
+2540 IF OL(I)>127 THEN PRINT "YOU CAN'T GET THAT!":GOTO 100
+2550 IF OL(I)<>R THEN PRINT "THAT ISN'T HERE!":GOTO 100
+2560 GOSUB 1200
+2570 IF C>=MI THEN PRINT "YOU CAN'T CARRY ANY MORE.":GOTO 100
+2580 OL(I)=-1:PRINT "TAKEN.":GOTO 100
+2600 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO DROP?":GOTO 100
+2610 GOSUB 1000:IF I=0 THEN 2630
+2620 IF OL(I)=-1 THEN OL(I)=R:PRINT "DROPPED.":GOTO 100
+2630 PRINT "YOU DON'T HAVE THAT!":GOTO 100
+2700 IF GL=1 THEN PRINT "YOU ARE WEARING RUBBER GLOVES."
+2710 PRINT "YOU ARE CARRYING:":F=0
+2720 FOR K=1 TO NO:IF OL(K)=-1 THEN PRINT "    ";OB$(K):F=1
+2730 NEXT
+2740 IF F=0 THEN PRINT "    NOTHING"
+2750 GOTO 100
+2900 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO EXAMINE?":GOTO 100
+3000 GOSUB 1000:GOSUB 1100:IF H=0 THEN PRINT "THAT ISN'T HERE!":GOTO 100
+3050 PRINT "YOU SEE NOTHING UNUSUAL.":GOTO 100
+3400 Q$="":INPUT "ARE YOU SURE";Q$
+3410 IF LEFT$(Q$,1)="Y" THEN END
+3420 GOTO 100
+3430 PRINT:PRINT "CONGRATULATIONS! YOU'VE WON!"
+3440 Q$="":PRINT:INPUT "WOULD YOU LIKE TO PLAY AGAIN";Q$
+3450 IF LEFT$(Q$,1)="Y" THEN RUN
+3460 END
+25000 DIM R$(NR),EX(NR,6),OB$(NO),OT$(NO),OL(NO)
+25010 FOR I=1 TO NO:READ OB$(I),OT$(I),OL(I):NEXT
+25020 FOR I=1 TO NR:READ R$(I):FOR J=1 TO 6:READ EX(I,J):NEXT J,I
+25030 RETURN
+"#;
+
+/// The book's puzzles, for the worlds that have them.
+/// Lines here replace the generic ones with the same number.
+const PUZZLES: &str = r#"
+2575 IF N$="RUB" THEN 3430
+3020 IF N$="BOT" THEN PRINT "THERE'S SOMETHING WRITTEN ON IT!":GOTO 100
+3030 IF N$="CAS" THEN PRINT "THERE'S A JEWEL INSIDE!":GOTO 100
+3040 IF N$="BAR" THEN PRINT "IT'S FILLED WITH RAINWATER.":GOTO 100
+340 IF V$="OPE" THEN 3700
+342 IF V$="REA" THEN 3500
+344 IF V$="POU" THEN 3900
+346 IF V$="CLI" THEN 4100
+348 IF V$="WAV" THEN 4600
+350 IF V$="JUM" THEN 4300
+352 IF V$="DIG" THEN 4400
+354 IF V$="ROW" THEN 4500
+356 IF V$="LEA" OR V$="EXI" THEN 4700
+358 IF V$="FIG" THEN 4800
+360 IF V$="WEA" THEN 4900
+2030 IF DE={GUARDED} THEN 2400
+2100 DE=0:IF OL({BOAT_OBJ})=R+128 THEN DE={BOAT_ROOM}
+2400 REM THE GUARD
+2410 IF OL({GUARD})={GATE} THEN PRINT "THE GUARD WON'T LET YOU!":GOTO 100
+2420 R={NARROW_HALL}:GOSUB 700:GOTO 100
+2910 IF N$<>"GRO" THEN 3000
+2920 IF R<>{OPEN_FIELD} THEN PRINT "IT LOOKS LIKE GROUND!":GOTO 100
+2930 IF OL({SWORD})<>0 THEN PRINT "THERE'S A HOLE HERE.":GOTO 100
+2940 PRINT "IT LOOKS LIKE SOMETHING'S BURIED HERE.":GOTO 100
+3500 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO READ?":GOTO 100
+3510 GOSUB 1000:GOSUB 1100:IF H=0 THEN PRINT "THAT ISN'T HERE!":GOTO 100
+3520 IF N$="DIA" THEN 3560
+3530 IF N$="DIC" THEN 3580
+3540 IF N$="BOT" THEN PRINT "IT READS: 'SECRET FORMULA'.":GOTO 100
+3550 PRINT "YOU CAN'T READ THAT!":GOTO 100
+3560 PRINT "IT SAYS: 'ADD SODIUM CHLORIDE PLUS THE"
+3570 PRINT "FORMULA TO RAINWATER, TO REACH THE":PRINT "OTHER WORLD.'":GOTO 100
+3580 PRINT "IT SAYS: SODIUM CHLORIDE IS":PRINT "COMMON TABLE SALT.":GOTO 100
+3700 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO OPEN?":GOTO 100
+3710 GOSUB 1000:GOSUB 1100:IF H=0 THEN PRINT "THAT ISN'T HERE!":GOTO 100
+3720 IF N$="CAB" THEN 3800
+3730 IF N$="CAS" THEN 3850
+3740 IF N$<>"BOX" THEN PRINT "YOU CAN'T OPEN THAT!":GOTO 100
+3750 IF OL({BOTTLE})<>0 THEN PRINT "THE BOX IS ALREADY OPEN.":GOTO 100
+3760 OL({BOTTLE})=R:PRINT "SOMETHING FELL OUT!":GOSUB 600:GOTO 100
+3800 IF OL({SALT})<>0 THEN PRINT "THE CABINET IS ALREADY OPEN.":GOTO 100
+3810 OL({SALT})=R:PRINT "THERE'S SOMETHING INSIDE!":GOSUB 600:GOTO 100
+3850 IF OL({RUBY})<>0 THEN PRINT "THE CASE IS ALREADY OPEN.":GOTO 100
+3860 IF GL=0 THEN PRINT "THE CASE IS ELECTRIFIED!":GOTO 100
+3870 PRINT "THE GLOVES INSULATE AGAINST THE"
+3880 PRINT "ELECTRICITY! THE CASE OPENS!":OL({RUBY})=R:GOSUB 600:GOTO 100
+3900 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO POUR?":GOTO 100
+3910 GOSUB 1000:GOSUB 1100:IF H=0 THEN PRINT "THAT ISN'T HERE!":GOTO 100
+3920 IF N$="SAL" AND SA=1 THEN PRINT "THE SALT SHAKER IS EMPTY.":GOTO 100
+3930 IF N$="SAL" AND R={GARAGE} THEN SA=1:GOTO 4000
+3940 IF N$="BOT" AND FO=1 THEN PRINT "THE BOTTLE IS EMPTY.":GOTO 100
+3950 IF N$="BOT" AND R={GARAGE} THEN FO=1:GOTO 4000
+3960 PRINT "YOU CAN'T POUR THAT!":GOTO 100
+4000 PRINT "POURED!":IF SA=0 OR FO=0 THEN 100
+4010 PRINT "THERE IS AN EXPLOSION!":PRINT "EVERYTHING GOES BLACK!"
+4020 PRINT "SUDDENLY YOU ARE. . .":PRINT ". . .SOMEWHERE ELSE!"
+4030 R={OPEN_FIELD}:GOSUB 700:GOTO 100
+4100 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO CLIMB?":GOTO 100
+4110 I={TREE}:GOSUB 1100:IF N$="TRE" AND H=1 THEN 4170
+4120 I={LADDER}:GOSUB 1100:IF N$<>"LAD" OR H=0 THEN 4180
+4130 IF R<>{FOREST_EDGE} THEN PRINT "WHATEVER FOR?":GOTO 100
+4150 PRINT "THE LADDER SINKS UNDER YOUR WEIGHT!"
+4160 PRINT "IT DISAPPEARS INTO THE GROUND!":OL({LADDER})=0:GOTO 100
+4170 PRINT "YOU CAN'T REACH THE BRANCHES!":GOTO 100
+4180 PRINT "IT WON'T DO ANY GOOD.":GOTO 100
+4300 IF R={FOREST_EDGE} THEN PRINT "YOU GRAB THE LOWEST BRANCH OF THE":GOTO 4330
+4310 IF R={TREE_BRANCH} THEN PRINT "YOU GRAB A HIGHER BRANCH OF THE":GOTO 4340
+4320 PRINT "WHEE! THAT WAS FUN!":GOTO 100
+4330 PRINT "TREE AND PULL YOURSELF UP. . . .":R={TREE_BRANCH}:GOTO 4350
+4340 PRINT "TREE AND PULL YOURSELF UP. . . .":R={TREE_TOP}
+4350 GOSUB 700:GOTO 100
+4400 IF NW=1 THEN N$="GRO"
+4410 IF N$<>"GRO" AND N$<>"HOL" THEN PRINT "YOU CAN'T DIG THAT!":GOTO 100
+4420 I={SHOVEL}:GOSUB 1100:IF H=0 THEN PRINT "YOU DON'T HAVE A SHOVEL!":GOTO 100
+4430 IF R<>{OPEN_FIELD} THEN PRINT "YOU DON'T FIND ANYTHING.":GOTO 100
+4440 IF OL({SWORD})<>0 THEN PRINT "THERE'S NOTHING ELSE THERE!":GOTO 100
+4450 PRINT "THERE'S SOMETHING THERE!":OL({SWORD})={OPEN_FIELD}:GOSUB 600:GOTO 100
+4500 IF NW=2 AND N$<>"BOA" THEN PRINT "HOW CAN YOU ROW THAT?":GOTO 100
+4510 IF R<>{BOAT_ROOM} THEN PRINT "YOU'RE NOT IN A BOAT!":GOTO 100
+4520 PRINT "YOU DON'T HAVE AN OAR!":GOTO 100
+4600 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO WAVE?":GOTO 100
+4610 IF N$<>"FAN" THEN PRINT "YOU CAN'T WAVE THAT!":GOTO 100
+4615 I={FAN}:GOSUB 1100:IF H=0 THEN PRINT "YOU DON'T HAVE A FAN!":GOTO 100
+4620 IF R<>{BOAT_ROOM} THEN PRINT "YOU FEEL A REFRESHING BREEZE!":GOTO 100
+4630 PRINT "A POWERFUL BREEZE PROPELS THE BOAT":PRINT "TO THE OPPOSITE SHORE!"
+4640 IF OL({BOAT_OBJ})={SOUTH_SHORE} THEN OL({BOAT_OBJ})={NORTH_SHORE}:GOTO 100
+4650 OL({BOAT_OBJ})={SOUTH_SHORE}:GOTO 100
+4700 IF NW=1 THEN N$="BOA"
+4702 IF R<>{BOAT_ROOM} THEN 4710
+4704 IF N$<>"BOA" THEN 4720
+4706 R=OL({BOAT_OBJ}) AND 127:GOSUB 700:GOTO 100
+4710 PRINT "PLEASE GIVE A DIRECTION!":GOTO 100
+4720 PRINT "HUH?":GOTO 100
+4800 IF NW=1 THEN PRINT "WHOM DO YOU WANT TO FIGHT?":GOTO 100
+4810 IF N$<>"GUA" THEN PRINT "YOU CAN'T FIGHT THEM!":GOTO 100
+4820 I={GUARD}:GOSUB 1100:IF H=0 THEN PRINT "THERE'S NO GUARD HERE!":GOTO 100
+4830 IF OL({SWORD})<>-1 THEN PRINT "YOU DON'T HAVE A WEAPON!":GOTO 100
+4840 PRINT "THE GUARD, NOTICING YOUR SWORD,"
+4850 PRINT "WISELY RETREATS INTO THE CASTLE.":OL({GUARD})=0:GOTO 100
+4900 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO WEAR?":GOTO 100
+4910 IF N$<>"GLO" THEN PRINT "YOU CAN'T WEAR THAT!":GOTO 100
+4920 IF GL=1 THEN 4950
+4930 I={GLOVES}:GOSUB 1100:IF H=0 THEN PRINT "YOU DON'T HAVE THE GLOVES.":GOTO 100
+4940 PRINT "YOU ARE NOW WEARING THE GLOVES.":GL=1:OL({GLOVES})=0:GOTO 100
+4950 PRINT "YOU ARE ALREADY WEARING THE RUBBER GLOVES.":GOTO 100
+"#;
+
+/// The check that keeps the box from dropping more bottles.
+const BOX_FIX: u32 = 3750;
+const INTRO_LINE: u32 = 24000;
+const OBJECT_LINE: u32 = 26000;
+const ROOM_LINE: u32 = 27000;
+
+pub fn export(world: &World) -> String {
+    let mut program = BTreeMap::new();
+    let mut add = |template: &str, values: &[(&str, String)]| {
+        for line in template.lines().filter(|line| !line.is_empty()) {
+            let mut line = line.to_string();
+            for (name, value) in values {
+                line = line.replace(&format!("{{{}}}", name), value);
+            }
+            let digits = line.find(' ').unwrap_or(line.len());
+            program.insert(line[..digits].parse::<u32>().unwrap(), line);
+        }
+    };
+
+    add(GENERIC, &[
+        ("NR", (world.rooms.len() - 1).to_string()),
+        ("NO", (world.objects.len() - 1).to_string()),
+        ("MI", MAX_INVENTORY.to_string()),
+        ("START", START_ROOM.to_string()),
+    ]);
+    if world.puzzles {
+        let id = |tag| world.objects.iter().position(|obj| obj.tag == tag).unwrap();
+        add(PUZZLES, &[
+            ("BOAT_OBJ", BOAT_OBJ.to_string()),
+            ("BOAT_ROOM", BOAT_ROOM.to_string()),
+            ("BOTTLE", BOTTLE.to_string()),
+            ("FAN", id("FAN").to_string()),
+            ("FOREST_EDGE", FOREST_EDGE.to_string()),
+            ("GARAGE", GARAGE.to_string()),
+            ("GATE", immobile!(CASTLE_GATE).to_string()),
+            ("GLOVES", GLOVES.to_string()),
+            ("GUARD", GUARD.to_string()),
+            ("GUARDED", GUARDED.to_string()),
+            ("LADDER", LADDER.to_string()),
+            ("NARROW_HALL", NARROW_HALL.to_string()),
+            ("NORTH_SHORE", immobile!(NORTH_BANK).to_string()),
+            ("OPEN_FIELD", OPEN_FIELD.to_string()),
+            ("RUBY", RUBY.to_string()),
+            ("SALT", SALT.to_string()),
+            ("SHOVEL", id("SHO").to_string()),
+            ("SOUTH_SHORE", immobile!(SOUTH_BANK).to_string()),
+            ("SWORD", SWORD.to_string()),
+            ("TREE", id("TRE").to_string()),
+            ("TREE_BRANCH", TREE_BRANCH.to_string()),
+            ("TREE_TOP", TREE_TOP.to_string()),
+        ]);
+        if !world.fixed(Fix::Box) {
+            program.remove(&BOX_FIX); // back to a new bottle every time
+        }
+    }
+
+    if !world.intro.is_empty() {
+        program.insert(30, "30 GOSUB 25000:GOSUB 24000".to_string());
+        let mut number = INTRO_LINE;
+        for text in as_printed(world.intro).split('\n') {
+            let line = if text.is_empty() {
+                format!("{} PRINT", number)
+            } else {
+                format!("{} PRINT {}", number, quote(text))
+            };
+            program.insert(number, line);
+            number += 10;
+        }
+        program.insert(number, format!("{} RETURN", number));
+    }
+
+    let objects = &world.objects[1..];
+    let step = data_step(objects.len());
+    for (i, obj) in objects.iter().enumerate() {
+        let start = if obj.start == INVENTORY { -1 } else { obj.start as i32 };
+        let number = OBJECT_LINE + step * i as u32;
+        program.insert(number, format!(
+            "{} DATA {},{},{}", number, quote(obj.name), quote(obj.tag), start
+        ));
+    }
+
+    let rooms = &world.rooms[1..];
+    let step = data_step(rooms.len());
+    for (i, room) in rooms.iter().enumerate() {
+        let number = ROOM_LINE + step * i as u32;
+        program.insert(number, format!(
+            "{} DATA {},{},{},{},{},{},{}",
+            number, quote(room.desc), room.n, room.s, room.e, room.w, room.u, room.d
+        ));
+    }
+
+    let mut listing = String::new();
+    for line in program.values() {
+        listing.push_str(line);
+        listing.push('\n');
+    }
+    listing
+}
+
+/// Spreads DATA lines out by tens, unless there are too many to fit in the block.
+fn data_step(count: usize) -> u32 {
+    (999 / count.max(1) as u32).clamp(1, 10)
+}
+
+/// BASIC strings can't contain double quotes at all.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{import, LISTING, STOCK};
+
+    #[test]
+    fn imports_back_as_the_same_world() {
+        let world = import::import(&export(&STOCK)).unwrap();
+        assert_eq!(world.rooms.len(), STOCK.rooms.len());
+        assert_eq!(world.objects.len(), STOCK.objects.len());
+        for (a, b) in world.rooms.iter().zip(STOCK.rooms.iter()) {
+            assert_eq!((a.desc, a.n, a.s, a.e, a.w, a.u, a.d), (b.desc, b.n, b.s, b.e, b.w, b.u, b.d));
+        }
+        for (a, b) in world.objects.iter().zip(STOCK.objects.iter()) {
+            assert_eq!((a.name, a.tag, a.start), (b.name, b.tag, b.start));
+        }
+    }
+
+    #[test]
+    fn the_shipped_listing_is_the_export_with_every_fix_off() {
+        assert_eq!(export(&World { original: !0, ..STOCK }), LISTING);
+    }
+
+    #[test]
+    fn quotes_can_only_be_apostrophes() {
+        assert_eq!(quote("SAY \"HI\""), "\"SAY 'HI'\"");
+    }
+}
@@ -0,0 +1,285 @@
+//! A C interface to `Session`, for hosting adventures in other programs.
+//! `lampton --header` prints the declarations for C, which are in HEADER below.
+//!
+//! Text goes in and out as NUL-terminated strings, except saved games,
+//! which are bytes with a length. A null session is ignored everywhere.
+//! A panic can't unwind into C, so each function catches any, and returns
+//! as it would for a null session.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use super::{import, Session, World, STOCK};
+
+pub const HEADER: &str = "\
+/* Lampton: How to Create Adventure Games, as a library. */
+
+#ifndef LAMPTON_H
+#define LAMPTON_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern \"C\" {
+#endif
+
+typedef struct lampton_session lampton_session;
+
+/* Starts a game of the book's adventure, or of the one in a BASIC listing
+ * if listing isn't NULL. NULL if the listing can't be read. Unless confirm,
+ * quitting and restarting don't ask ARE YOU SURE? */
+lampton_session *lampton_new(const char *listing, int confirm);
+
+/* Answers the game's prompt with a line of input. */
+void lampton_send(lampton_session *session, const char *input);
+
+/* What the game printed when started, or after the last lampton_send.
+ * Owned by the session, and good until the next call on it. */
+const char *lampton_output(const lampton_session *session);
+
+/* What the game is asking for next, such as \"\\nWHAT NOW? \". */
+const char *lampton_prompt(const lampton_session *session);
+
+/* Nonzero once the player has quit. */
+int lampton_is_over(const lampton_session *session);
+
+/* Copies up to size bytes of the saved game into buffer, and returns how
+ * many it takes, so a NULL buffer finds out how big to make it. */
+size_t lampton_save(const lampton_session *session, char *buffer, size_t size);
+
+/* Picks up a game saved by lampton_save. Zero if it isn't one. */
+int lampton_restore(lampton_session *session, const char *save, size_t size);
+
+/* Ends the game, and frees it and the world read from its listing. */
+void lampton_free(lampton_session *session);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
+";
+
+/// What C knows as lampton_session.
+pub struct Handle {
+    session: Session,
+    output: CString,
+    prompt: CString,
+    /// The world from the listing, if there was one, which goes when the session does.
+    imported: Option<&'static World>
+}
+
+impl Handle {
+    fn new(session: Session, imported: Option<&'static World>) -> Self {
+        let mut handle = Handle { session, output: CString::default(), prompt: CString::default(), imported };
+        let output = handle.session.take_output();
+        handle.update(output);
+        handle
+    }
+
+    fn update(&mut self, output: String) {
+        // the game never prints a NUL
+        self.output = CString::new(output).unwrap_or_default();
+        self.prompt = CString::new(self.session.prompt()).unwrap_or_default();
+    }
+}
+
+/// Runs a function's body, or returns `failed` if it panics.
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+/// # Safety
+/// `listing` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lampton_new(listing: *const c_char, confirm: c_int) -> *mut Handle {
+    guard(ptr::null_mut(), || {
+        let imported = if listing.is_null() {
+            None
+        } else {
+            match CStr::from_ptr(listing).to_str().map(import::import) {
+                Ok(Ok(world)) => Some(world),
+                _ => return ptr::null_mut()
+            }
+        };
+        let session = Session::new(imported.unwrap_or(&STOCK), confirm != 0);
+        Box::into_raw(Box::new(Handle::new(session, imported)))
+    })
+}
+
+/// # Safety
+/// `handle` must be null or from `lampton_new`, and `input` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lampton_send(handle: *mut Handle, input: *const c_char) {
+    guard((), || {
+        if let Some(handle) = handle.as_mut() {
+            if !input.is_null() {
+                let output = handle.session.step(&CStr::from_ptr(input).to_string_lossy());
+                handle.update(output);
+            }
+        }
+    })
+}
+
+/// # Safety
+/// `handle` must be null or from `lampton_new`.
+#[no_mangle]
+pub unsafe extern "C" fn lampton_output(handle: *const Handle) -> *const c_char {
+    guard(ptr::null(), || handle.as_ref().map_or(ptr::null(), |handle| handle.output.as_ptr()))
+}
+
+/// # Safety
+/// `handle` must be null or from `lampton_new`.
+#[no_mangle]
+pub unsafe extern "C" fn lampton_prompt(handle: *const Handle) -> *const c_char {
+    guard(ptr::null(), || handle.as_ref().map_or(ptr::null(), |handle| handle.prompt.as_ptr()))
+}
+
+/// # Safety
+/// `handle` must be null or from `lampton_new`.
+#[no_mangle]
+pub unsafe extern "C" fn lampton_is_over(handle: *const Handle) -> c_int {
+    guard(1, || handle.as_ref().is_none_or(|handle| handle.session.is_over()) as c_int)
+}
+
+/// # Safety
+/// `handle` must be null or from `lampton_new`, and `buffer` null or `size` bytes long.
+#[no_mangle]
+pub unsafe extern "C" fn lampton_save(handle: *const Handle, buffer: *mut c_char, size: usize) -> usize {
+    guard(0, || {
+        let save = match handle.as_ref() {
+            Some(handle) => handle.session.save(),
+            None => return 0
+        };
+        if !buffer.is_null() {
+            ptr::copy_nonoverlapping(save.as_ptr(), buffer as *mut u8, save.len().min(size));
+        }
+        save.len()
+    })
+}
+
+/// # Safety
+/// `handle` must be null or from `lampton_new`, and `save` `size` bytes long.
+#[no_mangle]
+pub unsafe extern "C" fn lampton_restore(handle: *mut Handle, save: *const c_char, size: usize) -> c_int {
+    guard(0, || {
+        let handle = match handle.as_mut() {
+            Some(handle) if !save.is_null() => handle,
+            _ => return 0
+        };
+        let save = slice::from_raw_parts(save as *const u8, size);
+        let restored = match std::str::from_utf8(save) {
+            Ok(save) => handle.session.restore(save),
+            Err(_) => false
+        };
+        handle.prompt = CString::new(handle.session.prompt()).unwrap_or_default();
+        restored as c_int
+    })
+}
+
+/// # Safety
+/// `handle` must be null or from `lampton_new`, and not used again.
+#[no_mangle]
+pub unsafe extern "C" fn lampton_free(handle: *mut Handle) {
+    guard((), || {
+        if !handle.is_null() {
+            let handle = Box::from_raw(handle);
+            let imported = handle.imported;
+            // the session goes first, so nothing is left looking at the world
+            drop(handle);
+            if let Some(world) = imported {
+                import::free(world);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn output(handle: *const Handle) -> String {
+        CStr::from_ptr(lampton_output(handle)).to_string_lossy().into_owned()
+    }
+
+    unsafe fn send(handle: *mut Handle, input: &str) {
+        let input = CString::new(input).unwrap();
+        lampton_send(handle, input.as_ptr());
+    }
+
+    #[test]
+    fn a_game_is_played_through_c() {
+        unsafe {
+            let handle = lampton_new(ptr::null(), 0);
+            assert!(output(handle).contains("LIVING ROOM"));
+            send(handle, "OPEN BOX");
+            assert!(output(handle).contains("SOMETHING FELL OUT!"));
+            assert_eq!(CStr::from_ptr(lampton_prompt(handle)).to_str(), Ok("\nWHAT NOW? "));
+
+            let mut save = vec![0; lampton_save(handle, ptr::null_mut(), 0)];
+            assert_eq!(lampton_save(handle, save.as_mut_ptr(), save.len()), save.len());
+            send(handle, "GET BOTTLE");
+            assert_eq!(lampton_restore(handle, save.as_ptr(), save.len()), 1);
+            send(handle, "I");
+            assert!(!output(handle).contains("BOTTLE"));
+            assert_eq!(lampton_restore(handle, b"junk".as_ptr() as *const c_char, 4), 0);
+
+            send(handle, "Q");
+            assert_eq!(lampton_is_over(handle), 1);
+            lampton_free(handle);
+        }
+    }
+
+    #[test]
+    fn a_game_from_a_listing_is_played_and_freed() {
+        unsafe {
+            let listing = CString::new("26000 DATA A RUBY,RUB,1\n27000 DATA IN A CAVE.,0,0,0,0,0,0\n").unwrap();
+            let handle = lampton_new(listing.as_ptr(), 0);
+            assert!(output(handle).contains("IN A CAVE."));
+            send(handle, "GET RUBY");
+            assert_eq!(output(handle), "TAKEN.\n");
+            assert!((*handle).imported.is_some());
+            lampton_free(handle);
+            let handle = lampton_new(ptr::null(), 0);
+            assert!((*handle).imported.is_none());
+            lampton_free(handle);
+        }
+    }
+
+    #[test]
+    fn the_header_declares_every_function_and_no_others() {
+        // each function's name and how many parameters it takes
+        let signature = |decl: &str| {
+            let (open, close) = (decl.find('(').unwrap(), decl.find(')').unwrap());
+            let name = decl[..open].split_whitespace().last().unwrap().trim_start_matches('*');
+            (name.to_string(), decl[open + 1..close].split(',').count())
+        };
+        let mut defined: Vec<_> = include_str!("ffi.rs").split("pub unsafe extern \"C\" fn ").skip(1)
+            .map(signature)
+            .collect();
+        let mut in_header: Vec<_> = HEADER.lines()
+            .filter(|line| line.contains("lampton_") && line.ends_with(");"))
+            .map(signature)
+            .collect();
+        defined.sort();
+        in_header.sort();
+        assert_eq!(defined.len(), 8);
+        assert_eq!(defined, in_header);
+    }
+
+    #[test]
+    fn null_and_bad_input_are_ignored() {
+        unsafe {
+            let listing = CString::new("27000 DATA NOWHERE,9,0,0,0,0,0").unwrap();
+            assert!(lampton_new(listing.as_ptr(), 0).is_null());
+            send(ptr::null_mut(), "N");
+            assert!(lampton_output(ptr::null()).is_null());
+            assert_eq!(lampton_is_over(ptr::null()), 1);
+            assert_eq!(lampton_save(ptr::null(), ptr::null_mut(), 0), 0);
+            lampton_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Plays in a web browser, from a small server on this machine only.
+//!
+//! `GET /` is the page. `POST /new` starts a game, and `POST /command?session=ID`
+//! plays the command in the request body. Both answer with what the game
+//! printed and its prompt, the game's ID in `X-Session`, and `X-Over` once
+//! it's over. Like the telnet server's games, these can't SAVE or RESTORE.
+//!
+//! Only pages from this server get answers: a request must name it as
+//! localhost or 127.0.0.1 in its Host, and in its Origin if it has one,
+//! so a site elsewhere can't start games by pointing its own name here.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, prelude::*, BufReader};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{Fix, Session, World};
+
+/// Games going at once. Anyone else is turned away.
+const MAX_SESSIONS: usize = 8;
+
+/// Threads answering requests. More wait their turn.
+const WORKERS: usize = 4;
+
+/// Games nobody has played for this long are thrown away.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Requests are small, and a browser holding a connection open shouldn't tie up a thread.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Plenty for a command.
+const MAX_BODY: usize = 1024;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>How to Create Adventure Games</title>
+<style>
+body { background: #000; color: #ddd; font: 16px monospace; max-width: 44em; margin: 1em auto; }
+#transcript { white-space: pre-wrap; height: 85vh; overflow-y: auto; }
+input { background: #000; color: #ddd; font: inherit; border: none; border-bottom: 1px solid #666; width: 100%; }
+</style>
+</head>
+<body>
+<div id="transcript"></div>
+<form id="form"><input id="command" autocomplete="off" autofocus></form>
+<script>
+let session = "";
+const transcript = document.getElementById("transcript");
+const command = document.getElementById("command");
+
+function show(text) {
+  transcript.textContent += text;
+  transcript.scrollTop = transcript.scrollHeight;
+}
+
+async function send(path, body) {
+  const response = await fetch(path, { method: "POST", body });
+  session = response.headers.get("X-Session") || session;
+  command.disabled = response.headers.has("X-Over");
+  show(await response.text());
+}
+
+document.getElementById("form").addEventListener("submit", event => {
+  event.preventDefault();
+  show(command.value + "\n");
+  send("/command?session=" + session, command.value);
+  command.value = "";
+});
+
+send("/new", "");
+</script>
+</body>
+</html>
+"#;
+
+const COMMAND: &str = "/command?session=";
+
+/// Each game going, and when it was last played.
+type Games = Mutex<HashMap<u64, (Session, Instant)>>;
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    session: Option<u64>,
+    over: bool,
+    body: String
+}
+
+impl Response {
+    fn text(status: &'static str, body: &str) -> Self {
+        Response { status, content_type: "text/plain", session: None, over: false, body: body.to_string() }
+    }
+}
+
+/// Listens on `port` until killed.
+pub fn serve(port: u16, world: &'static World, confirm: bool) -> io::Result<()> {
+    let world: &World = Box::leak(Box::new(World { original: world.original | Fix::Save.bit(), ..*world }));
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    eprintln!("lampton: playing at http://{}/", listener.local_addr()?);
+    let games = Arc::new(Games::default());
+    let (queue, streams) = mpsc::sync_channel(WORKERS);
+    let streams = Arc::new(Mutex::new(streams));
+    for _ in 0..WORKERS {
+        let (games, streams) = (Arc::clone(&games), Arc::clone(&streams));
+        thread::spawn(move || loop {
+            let stream = match streams.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).recv() {
+                Ok(stream) => stream,
+                Err(_) => break
+            };
+            // a request that panicked mustn't take the thread with it
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| answer(stream, &games, world, confirm)));
+        });
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => queue.send(stream).map_err(|_| io::Error::other("no workers left"))?,
+            Err(_) => continue
+        }
+    }
+    Ok(())
+}
+
+fn answer(mut stream: TcpStream, games: &Games, world: &'static World, confirm: bool) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut length = 0;
+    let (mut host, mut origin) = (None, None);
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().unwrap_or(0).min(MAX_BODY);
+            } else if name.eq_ignore_ascii_case("Host") {
+                host = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("Origin") {
+                origin = Some(value.trim().to_string());
+            }
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    // only what can be typed at the prompt, as over telnet
+    let body: String = body.iter().filter(|byte| (b' '..=b'~').contains(byte)).map(|&byte| byte as char).collect();
+
+    let port = stream.local_addr()?.port();
+    let from_here = host.is_some_and(|host| is_ours(&host, port))
+        && origin.is_none_or(|origin| origin.strip_prefix("http://").is_some_and(|host| is_ours(host, port)));
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        _ if !from_here => Response::text("403 Forbidden", "FORBIDDEN\n"),
+        (Some("GET"), Some("/")) => {
+            Response { content_type: "text/html", ..Response::text("200 OK", PAGE) }
+        }
+        (Some("POST"), Some("/new")) => start(games, world, confirm),
+        (Some("POST"), Some(path)) if path.starts_with(COMMAND) => {
+            let id = path[COMMAND.len()..].parse().unwrap_or(0);
+            play(games, id, |session| session.step(&body))
+        }
+        _ => Response::text("404 Not Found", "NOT FOUND\n")
+    };
+
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status, response.content_type, response.body.len()
+    );
+    if let Some(id) = response.session {
+        head += &format!("X-Session: {}\r\n", id);
+    }
+    if response.over {
+        head += "X-Over: 1\r\n";
+    }
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(b"\r\n")?;
+    stream.write_all(response.body.as_bytes())
+}
+
+/// Whether a Host names this server, rather than some other name
+/// that's been pointed at it.
+fn is_ours(host: &str, port: u16) -> bool {
+    let (name, at) = match host.rsplit_once(':') {
+        Some((name, at)) => (name, at.parse().ok()),
+        None => (host, Some(80))
+    };
+    at == Some(port) && (name.eq_ignore_ascii_case("localhost") || name == "127.0.0.1")
+}
+
+/// Starts a game, if there's room for another.
+fn start(games: &Games, world: &'static World, confirm: bool) -> Response {
+    let id = new_id();
+    {
+        let mut games = lock(games);
+        forget_idle(&mut games);
+        if games.len() >= MAX_SESSIONS {
+            return Response::text("503 Service Unavailable", "SORRY, EVERY GAME IS TAKEN. TRY AGAIN LATER.\n");
+        }
+        games.insert(id, (Session::new(world, confirm), Instant::now()));
+    }
+    play(games, id, Session::take_output)
+}
+
+/// Plays a turn of a game, forgetting it if that's the end of it,
+/// along with any left idle.
+fn play(games: &Games, id: u64, turn: impl FnOnce(&mut Session) -> String) -> Response {
+    let mut games = lock(games);
+    forget_idle(&mut games);
+    let now = Instant::now();
+    let (session, played) = match games.get_mut(&id) {
+        Some(game) => game,
+        None => return Response::text("404 Not Found", "THAT GAME IS OVER. RELOAD THE PAGE FOR ANOTHER.\n")
+    };
+    *played = now;
+    let text = turn(session) + session.prompt();
+    let over = session.is_over();
+    if over {
+        games.remove(&id);
+    }
+    Response { session: Some(id), over, ..Response::text("200 OK", &text) }
+}
+
+fn forget_idle(games: &mut HashMap<u64, (Session, Instant)>) {
+    let now = Instant::now();
+    games.retain(|_, &mut (_, played)| now.duration_since(played) < IDLE_TIMEOUT);
+}
+
+fn lock(games: &Games) -> MutexGuard<'_, HashMap<u64, (Session, Instant)>> {
+    games.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Hard to guess, so other pages can't play someone else's game.
+fn new_id() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STOCK;
+
+    /// The response to a request, answered over a connection.
+    /// PORT in the request is the port it's sent to.
+    fn request(games: &Arc<Games>, request: &str, body: &[u8]) -> io::Result<String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let mut client = TcpStream::connect(listener.local_addr()?)?;
+        let (stream, _) = listener.accept()?;
+        let games = Arc::clone(games);
+        let answered = thread::spawn(move || answer(stream, &games, &STOCK, false));
+        client.write_all(request.replace("PORT", &listener.local_addr()?.port().to_string()).as_bytes())?;
+        client.write_all(body)?;
+        let mut response = String::new();
+        client.read_to_string(&mut response)?;
+        answered.join().unwrap()?;
+        Ok(response)
+    }
+
+    fn new(games: &Arc<Games>) -> io::Result<String> {
+        request(games, "POST /new HTTP/1.1\r\nHost: 127.0.0.1:PORT\r\n\r\n", b"")
+    }
+
+    fn command(games: &Arc<Games>, session: &str, command: &[u8]) -> io::Result<String> {
+        let post = format!(
+            "POST {}{} HTTP/1.1\r\nHost: localhost:PORT\r\nOrigin: http://localhost:PORT\r\nContent-Length: {}\r\n\r\n",
+            COMMAND, session, command.len()
+        );
+        request(games, &post, command)
+    }
+
+    #[test]
+    fn a_game_is_played_over_http() -> io::Result<()> {
+        let games = Arc::new(Games::default());
+        let page = request(&games, "GET / HTTP/1.1\r\nHost: localhost:PORT\r\n\r\n", b"")?;
+        assert!(page.contains("Content-Type: text/html"));
+
+        let started = new(&games)?;
+        assert!(started.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(started.ends_with("\nWHAT NOW? "));
+        let session = started.lines().find_map(|line| line.strip_prefix("X-Session: ")).unwrap().to_string();
+
+        let opened = command(&games, &session, b"OPEN\xff\x07 BOX")?;
+        assert!(opened.contains("SOMETHING FELL OUT!"));
+        let quit = command(&games, &session, b"Q")?;
+        assert!(quit.contains("X-Over: 1\r\n"));
+        assert!(command(&games, &session, b"LOOK")?.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn only_pages_from_this_server_are_answered() -> io::Result<()> {
+        let games = Arc::new(Games::default());
+        let forbidden = |head: &str| -> io::Result<bool> {
+            Ok(request(&games, head, b"")?.starts_with("HTTP/1.1 403 Forbidden\r\n"))
+        };
+        assert!(forbidden("POST /new HTTP/1.1\r\n\r\n")?);
+        assert!(forbidden("POST /new HTTP/1.1\r\nHost: evil.example:PORT\r\n\r\n")?);
+        assert!(forbidden("POST /new HTTP/1.1\r\nHost: localhost:1\r\n\r\n")?);
+        assert!(forbidden("POST /new HTTP/1.1\r\nHost: localhost:PORT\r\nOrigin: http://evil.example\r\n\r\n")?);
+        assert!(forbidden("POST /new HTTP/1.1\r\nHost: localhost:PORT\r\nOrigin: null\r\n\r\n")?);
+        assert!(lock(&games).is_empty());
+        assert!(!forbidden("POST /new HTTP/1.1\r\nHost: LOCALHOST:PORT\r\nOrigin: http://127.0.0.1:PORT\r\n\r\n")?);
+        assert!(is_ours("localhost", 80));
+        Ok(())
+    }
+
+    #[test]
+    fn games_beyond_the_limit_are_turned_away() -> io::Result<()> {
+        let games = Arc::new(Games::default());
+        for _ in 0..MAX_SESSIONS {
+            assert!(new(&games)?.starts_with("HTTP/1.1 200 OK\r\n"));
+        }
+        let refused = new(&games)?;
+        assert!(refused.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(refused.ends_with("SORRY, EVERY GAME IS TAKEN. TRY AGAIN LATER.\n"));
+        assert_eq!(lock(&games).len(), MAX_SESSIONS);
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+//! Reads other adventures out of BASIC listings.
+//!
+//! Only the DATA statements matter, and they're expected where `--export`
+//! puts them, which is this port's layout rather than a transcription of
+//! the book's, so a listing typed in from elsewhere may need rearranging:
+//!
+//! - Line 26000 on: each object's name, 3-letter tag and starting room.
+//!   Immobile objects are 128 rooms over, and -1 is the inventory.
+//! - Line 27000 on: each room's description followed by its exits
+//!   N, S, E, W, U, D. An exit of 128 is one the guard blocks.
+//!
+//! The subroutine at 25000 that READs them, and the puzzles at 2500-4800,
+//! are skipped. Imported worlds only get the generic verbs.
+
+use super::{Object, Room, RoomId, World, GUARDED, INVENTORY};
+
+const OBJECT_DATA: u32 = 26000;
+const ROOM_DATA: u32 = 27000;
+const END_DATA: u32 = 28000;
+
+/// Values from DATA statements, tagged with their line number for errors.
+type Items = Vec<(u32, String)>;
+
+/// The world lives as long as the game does, so it's leaked.
+pub fn import(listing: &str) -> Result<&'static World, String> {
+    let mut object_items = Items::new();
+    let mut room_items = Items::new();
+    for line in listing.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let digits = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(line.len());
+        let number: u32 = line[..digits].parse()
+            .map_err(|_| format!("missing line number: {}", line))?;
+        let items = match number {
+            OBJECT_DATA..ROOM_DATA => &mut object_items,
+            ROOM_DATA..END_DATA => &mut room_items,
+            _ => continue
+        };
+        for statement in split_outside_quotes(&line[digits..], ':') {
+            let statement = statement.trim();
+            if statement.get(..4).is_some_and(|keyword| keyword.eq_ignore_ascii_case("DATA")) {
+                for item in split_outside_quotes(&statement[4..], ',') {
+                    items.push((number, unquote(item)));
+                }
+            }
+        }
+    }
+
+    if !room_items.len().is_multiple_of(7) {
+        return Err("rooms need a description and 6 exits each".to_string());
+    }
+    if !object_items.len().is_multiple_of(3) {
+        return Err("objects need a name, tag and starting room each".to_string());
+    }
+    let n_rooms = room_items.len() / 7;
+    if n_rooms == 0 || n_rooms >= GUARDED as usize {
+        return Err(format!("expected 1 to 127 rooms, found {}", n_rooms));
+    }
+
+    let mut rooms = vec![Room { desc: "NOWHERE?", n: 0, s: 0, e: 0, w: 0, u: 0, d: 0 }];
+    for room in room_items.chunks(7) {
+        let exit = |i: usize| -> Result<RoomId, String> {
+            let (line, ref item) = room[i];
+            match item.parse::<u8>() {
+                Ok(dest) if dest as usize <= n_rooms || dest == GUARDED => Ok(dest),
+                _ => Err(format!("line {}: no room {}", line, item))
+            }
+        };
+        rooms.push(Room {
+            desc: leak(&room[0].1),
+            n: exit(1)?, s: exit(2)?, e: exit(3)?, w: exit(4)?, u: exit(5)?, d: exit(6)?
+        });
+    }
+
+    let mut objects = vec![Object { name: "DUMMY", tag: "DUM", start: 0 }];
+    for object in object_items.chunks(3) {
+        let (line, ref start) = object[2];
+        let start = match start.parse::<i32>() {
+            Ok(-1) => INVENTORY,
+            Ok(room) if (0..256).contains(&room) && (room & 127) as usize <= n_rooms => {
+                room as RoomId
+            }
+            _ => return Err(format!("line {}: no room {}", line, start))
+        };
+        // the parser knows a name by its first three letters, which a shorter tag never matches
+        let tag = object[1].1.to_ascii_uppercase();
+        if tag.chars().count() != 3 {
+            return Err(format!("line {}: tags are 3 letters, not {}", object[1].0, tag));
+        }
+        objects.push(Object { name: leak(&object[0].1), tag: leak(&tag), start });
+    }
+
+    Ok(Box::leak(Box::new(World {
+        rooms: Box::leak(rooms.into_boxed_slice()),
+        objects: Box::leak(objects.into_boxed_slice()),
+        intro: "",
+        puzzles: false,
+        original: 0
+    })))
+}
+
+/// Gives back what `import` leaked, for hosts that start many games.
+///
+/// # Safety
+/// `world` must be from `import`, and nothing may use it again.
+pub(crate) unsafe fn free(world: &'static World) {
+    let world = Box::from_raw(world as *const World as *mut World);
+    let rooms = Box::from_raw(world.rooms as *const [Room] as *mut [Room]);
+    let objects = Box::from_raw(world.objects as *const [Object] as *mut [Object]);
+    // the first of each is NOWHERE? or DUMMY, which weren't leaked
+    for room in &rooms[1..] {
+        drop(Box::from_raw(room.desc as *const str as *mut str));
+    }
+    for object in &objects[1..] {
+        drop(Box::from_raw(object.name as *const str as *mut str));
+        drop(Box::from_raw(object.tag as *const str as *mut str));
+    }
+}
+
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.to_string().into_boxed_str())
+}
+
+/// Like `split`, but leaves quoted strings alone.
+pub(crate) fn split_outside_quotes(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == sep && !quoted {
+            parts.push(&s[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// DATA items can be bare (trimmed) or quoted (kept as-is).
+pub(crate) fn unquote(item: &str) -> String {
+    let item = item.trim();
+    match item.strip_prefix('"') {
+        Some(rest) => rest.strip_suffix('"').unwrap_or(rest).to_string(),
+        None => item.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Session;
+
+    const CAVE: &str = r#"
+10 REM TINY CAVE
+25000 FOR I=1 TO NO: READ OB$(I),O2$(I),OL(I): NEXT
+26000 DATA A LANTERN,LAN,1
+26010 DATA "A ROCK, HEAVY",ROC,130
+26020 DATA A RUBY,rub,3
+27000 DATA "AT THE CAVE MOUTH.",2,0,0,0,0,0
+27010 DATA IN A DARK TUNNEL.,0,1,3,0,0,0: REM TUNNEL
+27020 DATA IN A GLITTERING GROTTO.,0,0,0,2,0,0
+"#;
+
+    #[test]
+    fn reads_rooms_and_objects_from_data() {
+        let world = import(CAVE).unwrap();
+        assert_eq!(world.rooms.len(), 4);
+        assert_eq!(world.room(2), "IN A DARK TUNNEL.");
+        assert_eq!((world.rooms[2].n, world.rooms[2].s, world.rooms[2].e), (0, 1, 3));
+        assert_eq!(world.object(2), "A ROCK, HEAVY");
+        assert_eq!(world.objects[2].start, immobile!(2));
+        assert_eq!(world.objects[3].tag, "RUB");
+        assert!(!world.puzzles);
+    }
+
+    #[test]
+    fn says_what_is_wrong() {
+        let bad_exit = CAVE.replace("0,0,0,2,0,0", "0,0,0,9,0,0");
+        assert_eq!(import(&bad_exit).err().as_deref(), Some("line 27020: no room 9"));
+        let bad_tag = CAVE.replace("LAN,1", "LANTERN,1");
+        assert_eq!(import(&bad_tag).err().as_deref(), Some("line 26000: tags are 3 letters, not LANTERN"));
+        let short_tag = CAVE.replace("ROC,130", "RO,130");
+        assert_eq!(import(&short_tag).err().as_deref(), Some("line 26010: tags are 3 letters, not RO"));
+        assert!(import("10 PRINT \"HELLO\"").is_err());
+        assert!(import("27000 xÉÉ").is_err());
+    }
+
+    #[test]
+    fn only_the_book_has_its_puzzles() {
+        let mut session = Session::new(import(CAVE).unwrap(), false);
+        for command in ["N", "E", "GET RUBY", "EXAMINE RUBY"].iter() {
+            session.step(command);
+        }
+        assert!(!session.won());
+        assert_eq!(session.inventory(), vec![3]);
+    }
+}
@@ -0,0 +1,378 @@
+//! Writes a world out as Inform 7 source, for an adventure that has
+//! outgrown the two-word parser.
+//!
+//! Rooms become rooms and objects things, with the book's three-letter
+//! words still understood. The puzzles come along as rules, for worlds
+//! that have them, and so does the score if we're keeping one; the
+//! daemons, NPCs and dying stay behind. The book's text is all capitals,
+//! which Inform's own messages aren't, so it comes across in sentence case:
+//! names the book shouted, like SIMON, will want capitalising again.
+
+use super::wrap::BREAK;
+use super::{
+    Fix, Room, RoomId, World, BOAT_ROOM, CASTLE_GATE, FOREST_EDGE, GARAGE, GUARD, GUARDED,
+    INVENTORY, MAX_INVENTORY, MAX_SCORE, NARROW_HALL, NORTH_BANK, OPEN_FIELD, RANKS, SOUTH_BANK, START_ROOM,
+    TREE_BRANCH, TREE_TOP
+};
+
+/// Lines 2900 to 4950 as rules. {TAG} stands for the object with that tag,
+/// and lines about the score are left out if there isn't one.
+const PUZZLES: &str = "
+Chapter 3 - The Puzzles
+
+Section 1 - Reading and examining (lines 2900 and 3500)
+
+Understand the command \"read\" as something new.
+Understand \"read [something]\" as examining.
+The description of the {DIA} is \"It says: [']Add sodium chloride plus the formula to rainwater, to reach the other world.[']\".
+The description of the {DIC} is \"It says: sodium chloride is common table salt.\".
+The description of the {BOT} is \"It reads: [']Secret formula['].\".
+The description of the {CAS} is \"There's a jewel inside!\".
+The description of the {BAR} is \"It's filled with rainwater.\".
+
+Section 2 - Opening (line 3700)
+
+Instead of opening the {BOX}:
+{BOX_CHECK}
+\tmove the {BOT} to the location;
+\tsay \"Something fell out!\".
+
+Instead of opening the {CAB}:
+\tif the {SAL} is not off-stage, say \"The cabinet is already open.\" instead;
+\tmove the {SAL} to the location;
+\tsay \"There's something inside!\".
+
+Instead of opening the {CAS}:
+\tif the {RUB} is not off-stage, say \"The case is already open.\" instead;
+\tif the player is not wearing the {GLO}, say \"The case is electrified!\" instead;
+\tsay \"The gloves insulate against the electricity! The case opens!\";
+\tincrease the score by 15;
+\tmove the {RUB} to the location.
+
+Instead of opening something, say \"You can't open that!\".
+
+The {GLO} is wearable.
+
+Section 3 - Pouring (line 3900)
+
+Pouring is an action applying to one thing.
+Understand \"pour [something]\" as pouring.
+A thing can be poured.
+
+Check pouring when the noun is not the {SAL} and the noun is not the {BOT}:
+\tsay \"You can't pour that!\" instead.
+Check pouring a poured thing:
+\tsay \"[The noun] is empty.\" instead.
+Check pouring when the location is not {GARAGE}:
+\tsay \"You can't pour that!\" instead.
+Carry out pouring:
+\tnow the noun is poured.
+Report pouring:
+\tsay \"Poured!\";
+\tif the {SAL} is poured and the {BOT} is poured:
+\t\tsay \"There is an explosion! Everything goes black! Suddenly you are . . . somewhere else!\";
+\t\tincrease the score by 25;
+\t\tmove the player to {OPEN_FIELD}.
+
+Section 4 - Climbing and jumping (lines 4100 and 4300)
+
+Instead of climbing the {TRE}, say \"You can't reach the branches!\".
+
+Instead of climbing the {LAD}:
+\tif the location is not {FOREST_EDGE}, say \"Whatever for?\" instead;
+\tsay \"The ladder sinks under your weight! It disappears into the ground!\";
+\tremove the {LAD} from play.
+
+Instead of jumping when the location is {FOREST_EDGE}:
+\tsay \"You grab the lowest branch of the tree and pull yourself up . . . .\";
+\tmove the player to {TREE_BRANCH}.
+
+Instead of jumping when the location is {TREE_BRANCH}:
+\tsay \"You grab a higher branch of the tree and pull yourself up . . . .\";
+\tmove the player to {TREE_TOP}.
+
+Section 5 - Digging (line 4400)
+
+Digging is an action applying to nothing.
+Understand \"dig\" and \"dig ground\" and \"dig hole\" as digging.
+Understand \"dig [text]\" as a mistake (\"You can't dig that!\").
+
+Check digging when the {SHO} is not enclosed by the location:
+\tsay \"You don't have a shovel!\" instead.
+Check digging when the location is not {OPEN_FIELD}:
+\tsay \"You don't find anything.\" instead.
+Check digging when the {SWO} is not off-stage:
+\tsay \"There's nothing else there!\" instead.
+Carry out digging:
+\tincrease the score by 15;
+\tmove the {SWO} to the location.
+Report digging:
+\tsay \"There's something there!\".
+
+Section 6 - The boat (lines 2100, 4600 and 4700)
+
+Instead of entering the {BOA}, move the player to {BOAT_ROOM}.
+
+Instead of exiting when the location is {BOAT_ROOM}, move the player to the location of the {BOA}.
+
+Instead of waving the {FAN}:
+\tif the location is not {BOAT_ROOM}, say \"You feel a refreshing breeze!\" instead;
+\tsay \"A powerful breeze propels the boat to the opposite shore!\";
+\tif the {BOA} is in {SOUTH_BANK}, move the {BOA} to {NORTH_BANK};
+\totherwise move the {BOA} to {SOUTH_BANK}.
+
+Instead of waving something, say \"You can't wave that!\".
+
+Section 7 - The guard (lines 2410 and 4800)
+
+Instead of attacking the {GUA}:
+\tif the player does not carry the {SWO}, say \"You don't have a weapon!\" instead;
+\tsay \"The guard, noticing your sword, wisely retreats into the castle.\";
+\tincrease the score by 20;
+\tremove the {GUA} from play.
+";
+
+/// Lowest score first, as Inform wants them.
+const RANKINGS: &str = "
+Table of Rankings
+Score\tRank
+";
+
+const DIRECTIONS: [&str; 6] = ["north", "south", "east", "west", "up", "down"];
+
+pub fn export(world: &World) -> String {
+    let mut source = String::from("\"How to Create Adventure Games\" by Christopher Lampton\n");
+    let room = |id: RoomId| format!("Room {}", id);
+    let things = names(world);
+    let score = world.fixed(Fix::Score);
+
+    source += "\nChapter 1 - The Map\n\n";
+    source += &format!("The player is in {}.\n", room(START_ROOM));
+    let exits = |r: &Room| [r.n, r.s, r.e, r.w, r.u, r.d];
+    // where an exit leads, if anywhere
+    let leads = |dest: RoomId| match dest {
+        GUARDED if world.puzzles => Some(NARROW_HALL),
+        0 => None,
+        _ if (dest as usize) < world.rooms.len() => Some(dest),
+        _ => None
+    };
+    for (id, r) in world.rooms.iter().enumerate().skip(1) {
+        let id = id as RoomId;
+        let desc = r.desc.trim_end_matches('.');
+        source += &format!("\n{} is a room. {}\n", room(id), text(&sentence_case(&format!("YOU ARE {}", r.desc))));
+        source += &format!("The printed name of {} is {}.\n", room(id), text(&title_case(desc)));
+        for (d, &dest) in exits(r).iter().enumerate() {
+            let way = if d < 4 { "of" } else { "from" };
+            let dir = capitalise(DIRECTIONS[d]);
+            match leads(dest) {
+                Some(to) => source += &format!("{} {} {} is {}.\n", dir, way, room(id), room(to)),
+                None => {
+                    // Inform would take a way back as given
+                    let back = d ^ 1;
+                    let comes_back = world.rooms.iter().skip(1)
+                        .any(|other| leads(exits(other)[back]) == Some(id));
+                    if comes_back {
+                        source += &format!("{} {} {} is nowhere.\n", dir, way, room(id));
+                    }
+                }
+            }
+        }
+    }
+
+    source += "\nChapter 2 - The Objects\n\n";
+    source += &format!("The carrying capacity of the player is {}.\n", MAX_INVENTORY);
+    for (obj, name) in world.objects.iter().zip(&things).skip(1) {
+        source += "\n";
+        source += &match obj.start {
+            0 => format!("The {} is a thing.", name),
+            INVENTORY => format!("The player carries the {}.", name),
+            start if start > 127 => format!("The {} is in {}. It is fixed in place.", name, room(start & 127)),
+            start => format!("The {} is in {}.", name, room(start))
+        };
+        let printed = printed_name(obj.name);
+        if printed != *name {
+            source += &format!(" The printed name of the {} is {}.", name, text(&printed));
+        }
+        source += &format!("\nUnderstand {} as the {}.\n", text(&obj.tag.to_ascii_lowercase()), name);
+    }
+
+    if world.puzzles {
+        let mut puzzles = PUZZLES.replace("{BOX_CHECK}\n", if world.fixed(Fix::Box) {
+            "\tif the {BOT} is not off-stage, say \"The box is already open.\" instead;\n"
+        } else {
+            "" // line 3740 drops a new bottle every time
+        });
+        for (obj, name) in world.objects.iter().zip(&things) {
+            puzzles = puzzles.replace(&format!("{{{}}}", obj.tag), name);
+        }
+        for (placeholder, id) in [
+            ("BOAT_ROOM", BOAT_ROOM), ("FOREST_EDGE", FOREST_EDGE), ("GARAGE", GARAGE),
+            ("NORTH_BANK", NORTH_BANK), ("OPEN_FIELD", OPEN_FIELD), ("SOUTH_BANK", SOUTH_BANK),
+            ("TREE_BRANCH", TREE_BRANCH), ("TREE_TOP", TREE_TOP)
+        ] {
+            puzzles = puzzles.replace(&format!("{{{}}}", placeholder), &room(id));
+        }
+        for line in puzzles.lines().filter(|line| score || !line.contains("the score")) {
+            source += line;
+            source += "\n";
+        }
+        for (id, r) in world.rooms.iter().enumerate() {
+            for (d, &dest) in exits(r).iter().enumerate() {
+                if dest == GUARDED {
+                    source += &format!(
+                        "\nInstead of going {} from {} when the {} is in {}, say \"The guard won't let you!\".\n",
+                        DIRECTIONS[d], room(id as RoomId), things[GUARD], room(CASTLE_GATE)
+                    );
+                }
+            }
+        }
+    }
+
+    // line 2575, with the rest of the puzzles
+    let ruby = world.objects.iter().position(|obj| obj.tag == "RUB").filter(|&id| id != 0 && world.puzzles);
+    if let Some(ruby) = ruby {
+        source += "\nChapter 4 - Winning\n\n";
+        source += &format!("After taking the {}:\n", things[ruby]);
+        if score {
+            source += "\tincrease the score by 25;\n";
+        }
+        source += "\tend the story finally saying \"Congratulations! You've won!\".\n";
+    }
+
+    if score {
+        source += &format!("\nUse scoring. The maximum score is {}.\n", MAX_SCORE);
+        source += RANKINGS;
+        for (min, rank) in RANKS.iter().rev() {
+            source += &format!("{}\t{}\n", min, text(&title_case(rank)));
+        }
+    }
+
+    if !world.intro.is_empty() {
+        let paragraphs: Vec<String> = world.intro.trim().split("\n\n")
+            .map(|paragraph| sentence_case(&paragraph.replace(BREAK, " ")))
+            .collect();
+        let paragraphs: Vec<String> = paragraphs.iter().map(|paragraph| escape(paragraph)).collect();
+        source += &format!("\nWhen play begins, say \"{}\".\n", paragraphs.join("[paragraph break]"));
+    }
+    source
+}
+
+/// What each object is called in the source: its name without the article,
+/// unless another object already has that name.
+fn names(world: &World) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (id, obj) in world.objects.iter().enumerate() {
+        let words: Vec<String> = printed_name(obj.name)
+            .split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect();
+        let mut name = words.join(" ");
+        if name.is_empty() || names.contains(&name) {
+            name = format!("thing {}", id);
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// A name without its article, in lower case.
+fn printed_name(name: &str) -> String {
+    let name = name.to_ascii_lowercase();
+    ["a ", "an ", "some ", "the "].iter()
+        .find_map(|article| name.strip_prefix(article))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+fn capitalise(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map_or(String::new(), |first| first.to_ascii_uppercase().to_string() + chars.as_str())
+}
+
+/// "IN YOUR LIVING ROOM" as "In Your Living Room".
+fn title_case(s: &str) -> String {
+    let small = ["a", "an", "and", "at", "by", "in", "of", "on", "the", "to"];
+    s.to_ascii_lowercase().split(' ').enumerate()
+        .map(|(i, word)| if i > 0 && small.contains(&word) { word.to_string() } else { capitalise(word) })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Lower case, but for the start of each sentence and "I".
+fn sentence_case(s: &str) -> String {
+    let mut out = String::new();
+    let mut start = true;
+    for word in s.to_ascii_lowercase().split(' ') {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out += &if start || word == "i" || word.starts_with("i'") { capitalise(word) } else { word.to_string() };
+        if !word.is_empty() {
+            start = word.ends_with(['.', '!', '?']);
+        }
+    }
+    out
+}
+
+fn text(s: &str) -> String {
+    format!("\"{}\"", escape(s))
+}
+
+/// Inform would print a lone ' as ", and take brackets for substitutions.
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '\'' => escaped += "[']",
+            '"' => escaped += "[quotation mark]",
+            '[' => escaped += "[bracket]",
+            ']' => escaped += "[close bracket]",
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{import, STOCK};
+
+    #[test]
+    fn rooms_objects_and_puzzles_come_across() {
+        let source = export(&STOCK);
+        assert!(source.contains("The player is in Room 1.\n"));
+        assert!(source.contains("The printed name of Room 1 is \"In Your Living Room\".\n"));
+        assert!(source.contains("Understand \"bot\" as the small bottle."));
+        assert!(source.contains("Chapter 3 - The Puzzles"));
+        assert!(source.contains("Chapter 4 - Winning"));
+        assert!(!source.contains('{'));
+    }
+
+    #[test]
+    fn read_is_taken_from_the_standard_rules_before_it_is_given_again() {
+        let source = export(&STOCK);
+        let new = source.find("Understand the command \"read\" as something new.\n").unwrap();
+        let examining = source.find("Understand \"read [something]\" as examining.\n").unwrap();
+        assert!(new < examining);
+        assert!(!source.contains("as \"examine\""));
+    }
+
+    #[test]
+    fn a_world_without_the_puzzles_cannot_be_won() {
+        let world = import("26000 DATA A RUBY,RUB,2\n27000 DATA IN A CAVE.,0,0,0,0,0,0\n27010 DATA IN A GROTTO.,0,0,0,0,0,0\n").unwrap();
+        let source = export(world);
+        assert!(source.contains("The ruby is in Room 2."));
+        assert!(!source.contains("Chapter 3"));
+        assert!(!source.contains("Chapter 4"));
+    }
+
+    #[test]
+    fn text_is_cased_and_escaped_for_inform() {
+        assert_eq!(title_case("AT THE END OF THE ROAD"), "At the End of the Road");
+        assert_eq!(sentence_case("I SEE. IT'S HERE"), "I see. It's here");
+        assert_eq!(text("IT'S [\"X\"]"), "\"IT[']S [bracket][quotation mark]X[quotation mark][close bracket]\"");
+        assert_eq!(printed_name("SOME GLOVES"), "gloves");
+    }
+}
@@ -0,0 +1,102 @@
+//! A protocol for bots and tools: each line in is a command, and each line
+//! out a JSON object with what the game printed and where things stand,
+//! starting with one for the introduction. For example:
+//!
+//! ```text
+//! {"text":"TAKEN.\n","prompt":"\nWHAT NOW? ","room":1,"description":"IN YOUR LIVING ROOM.",
+//!  "exits":["NORTH","SOUTH","EAST"],"objects":[{"id":2,"tag":"BOX","name":"A SMALL BOX"}],
+//!  "inventory":[{"id":1,"tag":"DIA","name":"AN OLD DIARY"}],"score":0,"moves":1,
+//!  "flags":{"salted":false,"formulated":false,"gloved":false},"won":false,"dead":false,"over":false}
+//! ```
+//!
+//! (all on one line).
+
+use std::io;
+
+use super::{ObjId, Session, Terminal, World};
+
+/// Plays a session on a terminal, a line of JSON for each command.
+pub fn play(term: &mut dyn Terminal, world: &'static World, confirm: bool) -> io::Result<()> {
+    let mut session = Session::new(world, confirm);
+    let text = session.take_output();
+    term.print(&report(&session, &text))?;
+    while !session.is_over() {
+        match term.read_line()? {
+            Some(input) => {
+                let text = session.step(&input);
+                term.print(&report(&session, &text))?;
+            }
+            None => break
+        }
+    }
+    Ok(())
+}
+
+fn report(session: &Session, text: &str) -> String {
+    let world = session.world();
+    let state = &session.state;
+    let objects = |ids: Vec<ObjId>| -> String {
+        let objects: Vec<String> = ids.iter()
+            .map(|&id| {
+                let obj = &world.objects[id];
+                format!("{{\"id\":{},\"tag\":{},\"name\":{}}}", id, string(obj.tag), string(obj.name))
+            })
+            .collect();
+        format!("[{}]", objects.join(","))
+    };
+    let exits: Vec<String> = session.exits().iter().map(|exit| string(exit)).collect();
+    format!(
+        "{{\"text\":{},\"prompt\":{},\"room\":{},\"description\":{},\"exits\":[{}],\
+         \"objects\":{},\"inventory\":{},\"score\":{},\"moves\":{},\
+         \"flags\":{{\"salted\":{},\"formulated\":{},\"gloved\":{}}},\
+         \"won\":{},\"dead\":{},\"over\":{}}}\n",
+        string(text), string(session.prompt()), session.here(),
+        string(world.rooms[session.here() as usize].desc), exits.join(","),
+        objects(session.objects_here()), objects(session.inventory()),
+        session.score(), session.moves(),
+        state.salted, state.formulated, state.gloved,
+        session.won(), session.dead(), session.is_over()
+    )
+}
+
+/// A JSON string literal.
+fn string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            '\n' => quoted += "\\n",
+            c if (c as u32) < 0x20 => quoted += &format!("\\u{:04x}", c as u32),
+            c => quoted.push(c)
+        }
+    }
+    quoted + "\""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STOCK;
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(string("SAY \"HI\"\\\n"), r#""SAY \"HI\"\\\n""#);
+        assert_eq!(string("\x1b[K\t"), r#""\u001b[K\u0009""#);
+        assert_eq!(string("É"), "\"É\"");
+    }
+
+    #[test]
+    fn each_report_is_a_line_with_where_things_stand() {
+        let mut session = Session::new(&STOCK, false);
+        session.take_output();
+        let text = session.step("OPEN BOX");
+        let report = report(&session, &text);
+        assert!(report.starts_with("{\"text\":\"SOMETHING FELL OUT!"));
+        assert!(report.contains("\"room\":1,\"description\":\"IN YOUR LIVING ROOM.\""));
+        assert!(report.contains("{\"id\":1,\"tag\":\"DIA\",\"name\":\"AN OLD DIARY\"}"));
+        assert!(report.contains("\"moves\":1,"));
+        assert!(report.ends_with("\"over\":false}\n"));
+        assert_eq!(report.matches('\n').count(), 1);
+    }
+}
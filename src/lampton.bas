@@ -0,0 +1,252 @@
+10 REM HOW TO CREATE ADVENTURE GAMES
+15 REM BY CHRISTOPHER LAMPTON (1986)
+20 NR=19:NO=17:MI=5
+30 GOSUB 25000:GOSUB 24000
+40 R=1:GOSUB 700
+100 A$="":PRINT:INPUT "WHAT NOW";A$
+110 NW=0:W$="":A$=A$+" "
+120 FOR I=1 TO LEN(A$):C$=MID$(A$,I,1)
+130 IF C$<>" " THEN W$=W$+C$:GOTO 160
+140 IF W$<>"" THEN NW=NW+1:IF NW<4 THEN WD$(NW)=LEFT$(W$,3)
+150 W$=""
+160 NEXT
+170 IF NW=0 THEN 100
+180 V$=WD$(1):N$="":IF NW>1 THEN N$=WD$(2)
+190 D$=V$:IF V$="GO" AND NW>1 THEN D$=N$
+200 D=0
+210 IF N$="SHA" THEN N$="SAL"
+220 IF N$="FOR" THEN N$="BOT"
+230 IF D$="N" OR D$="NOR" THEN D=1
+240 IF D$="S" OR D$="SOU" THEN D=2
+250 IF D$="E" OR D$="EAS" THEN D=3
+260 IF D$="W" OR D$="WES" THEN D=4
+270 IF D$="U" OR D$="UP" THEN D=5
+280 IF D$="D" OR D$="DOW" THEN D=6
+290 IF D$="BOA" THEN D=7
+295 IF D>0 THEN 2000
+300 IF NW>2 THEN 390
+305 IF NW=1 AND (V$="Q" OR V$="QUI") THEN 3400
+310 IF NW=1 AND (V$="I" OR V$="INV") THEN 2700
+315 IF NW=1 AND (V$="L" OR V$="LOO") THEN GOSUB 700:GOTO 100
+320 IF NW=1 AND V$="GO" THEN PRINT "GO WHERE?":GOTO 100
+325 IF V$="EXA" OR V$="LOO" THEN 2900
+330 IF V$="GET" OR V$="TAK" THEN 2500
+335 IF V$="DRO" THEN 2600
+340 IF V$="OPE" THEN 3700
+342 IF V$="REA" THEN 3500
+344 IF V$="POU" THEN 3900
+346 IF V$="CLI" THEN 4100
+348 IF V$="WAV" THEN 4600
+350 IF V$="JUM" THEN 4300
+352 IF V$="DIG" THEN 4400
+354 IF V$="ROW" THEN 4500
+356 IF V$="LEA" OR V$="EXI" THEN 4700
+358 IF V$="FIG" THEN 4800
+360 IF V$="WEA" THEN 4900
+390 PRINT "I DON'T KNOW HOW TO DO THAT.":GOTO 100
+500 PRINT "YOU CAN GO:";
+510 IF EX(R,1)>0 THEN PRINT " NORTH";
+520 IF EX(R,2)>0 THEN PRINT " SOUTH";
+530 IF EX(R,3)>0 THEN PRINT " EAST";
+540 IF EX(R,4)>0 THEN PRINT " WEST";
+550 IF EX(R,6)>0 THEN PRINT " DOWN";
+560 IF EX(R,5)>0 THEN PRINT " UP";
+570 RETURN
+600 PRINT:PRINT "YOU CAN SEE:":F=0
+610 FOR K=1 TO NO:IF (OL(K) AND 127)=R THEN PRINT "    ";OB$(K):F=1
+620 NEXT
+630 IF F=0 THEN PRINT "    THERE IS NOTHING OF INTEREST HERE."
+640 RETURN
+700 PRINT:PRINT "YOU ARE ";R$(R)
+710 GOSUB 500:GOSUB 600:RETURN
+1000 FOR I=1 TO NO:IF OT$(I)=N$ THEN RETURN
+1010 NEXT:I=0:RETURN
+1100 H=0:IF I=0 THEN RETURN
+1110 IF OL(I)=-1 OR (OL(I) AND 127)=R THEN H=1
+1120 RETURN
+1200 C=0:FOR K=1 TO NO:IF OL(K)=-1 THEN C=C+1
+1210 NEXT:RETURN
+2000 IF D=7 THEN 2100
+2010 DE=EX(R,D)
+2020 IF DE>0 AND DE<=NR THEN R=DE:GOSUB 700:GOTO 100
+2030 IF DE=128 THEN 2400
+2040 PRINT "YOU CAN'T GO THERE!":GOTO 100
+2100 DE=0:IF OL(12)=R+128 THEN DE=13
+2110 GOTO 2020
+2400 REM THE GUARD
+2410 IF OL(14)=144 THEN PRINT "THE GUARD WON'T LET YOU!":GOTO 100
+2420 R=17:GOSUB 700:GOTO 100
+2500 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO GET?":GOTO 100
+2520 GOSUB 1000:IF I=0 THEN PRINT "YOU CAN'T GET THAT!":GOTO 100
+2530 IF OL(I)=-1 THEN PRINT "YOU ALREADY HAVE IT!":GOTO 100
+2540 IF OL(I)>127 THEN PRINT "YOU CAN'T GET THAT!":GOTO 100
+2550 IF OL(I)<>R THEN PRINT "THAT ISN'T HERE!":GOTO 100
+2560 GOSUB 1200
+2570 IF C>=MI THEN PRINT "YOU CAN'T CARRY ANY MORE.":GOTO 100
+2575 IF N$="RUB" THEN 3430
+2580 OL(I)=-1:PRINT "TAKEN.":GOTO 100
+2600 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO DROP?":GOTO 100
+2610 GOSUB 1000:IF I=0 THEN 2630
+2620 IF OL(I)=-1 THEN OL(I)=R:PRINT "DROPPED.":GOTO 100
+2630 PRINT "YOU DON'T HAVE THAT!":GOTO 100
+2700 IF GL=1 THEN PRINT "YOU ARE WEARING RUBBER GLOVES."
+2710 PRINT "YOU ARE CARRYING:":F=0
+2720 FOR K=1 TO NO:IF OL(K)=-1 THEN PRINT "    ";OB$(K):F=1
+2730 NEXT
+2740 IF F=0 THEN PRINT "    NOTHING"
+2750 GOTO 100
+2900 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO EXAMINE?":GOTO 100
+2910 IF N$<>"GRO" THEN 3000
+2920 IF R<>6 THEN PRINT "IT LOOKS LIKE GROUND!":GOTO 100
+2930 IF OL(11)<>0 THEN PRINT "THERE'S A HOLE HERE.":GOTO 100
+2940 PRINT "IT LOOKS LIKE SOMETHING'S BURIED HERE.":GOTO 100
+3000 GOSUB 1000:GOSUB 1100:IF H=0 THEN PRINT "THAT ISN'T HERE!":GOTO 100
+3020 IF N$="BOT" THEN PRINT "THERE'S SOMETHING WRITTEN ON IT!":GOTO 100
+3030 IF N$="CAS" THEN PRINT "THERE'S A JEWEL INSIDE!":GOTO 100
+3040 IF N$="BAR" THEN PRINT "IT'S FILLED WITH RAINWATER.":GOTO 100
+3050 PRINT "YOU SEE NOTHING UNUSUAL.":GOTO 100
+3400 Q$="":INPUT "ARE YOU SURE";Q$
+3410 IF LEFT$(Q$,1)="Y" THEN END
+3420 GOTO 100
+3430 PRINT:PRINT "CONGRATULATIONS! YOU'VE WON!"
+3440 Q$="":PRINT:INPUT "WOULD YOU LIKE TO PLAY AGAIN";Q$
+3450 IF LEFT$(Q$,1)="Y" THEN RUN
+3460 END
+3500 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO READ?":GOTO 100
+3510 GOSUB 1000:GOSUB 1100:IF H=0 THEN PRINT "THAT ISN'T HERE!":GOTO 100
+3520 IF N$="DIA" THEN 3560
+3530 IF N$="DIC" THEN 3580
+3540 IF N$="BOT" THEN PRINT "IT READS: 'SECRET FORMULA'.":GOTO 100
+3550 PRINT "YOU CAN'T READ THAT!":GOTO 100
+3560 PRINT "IT SAYS: 'ADD SODIUM CHLORIDE PLUS THE"
+3570 PRINT "FORMULA TO RAINWATER, TO REACH THE":PRINT "OTHER WORLD.'":GOTO 100
+3580 PRINT "IT SAYS: SODIUM CHLORIDE IS":PRINT "COMMON TABLE SALT.":GOTO 100
+3700 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO OPEN?":GOTO 100
+3710 GOSUB 1000:GOSUB 1100:IF H=0 THEN PRINT "THAT ISN'T HERE!":GOTO 100
+3720 IF N$="CAB" THEN 3800
+3730 IF N$="CAS" THEN 3850
+3740 IF N$<>"BOX" THEN PRINT "YOU CAN'T OPEN THAT!":GOTO 100
+3760 OL(7)=R:PRINT "SOMETHING FELL OUT!":GOSUB 600:GOTO 100
+3800 IF OL(4)<>0 THEN PRINT "THE CABINET IS ALREADY OPEN.":GOTO 100
+3810 OL(4)=R:PRINT "THERE'S SOMETHING INSIDE!":GOSUB 600:GOTO 100
+3850 IF OL(16)<>0 THEN PRINT "THE CASE IS ALREADY OPEN.":GOTO 100
+3860 IF GL=0 THEN PRINT "THE CASE IS ELECTRIFIED!":GOTO 100
+3870 PRINT "THE GLOVES INSULATE AGAINST THE"
+3880 PRINT "ELECTRICITY! THE CASE OPENS!":OL(16)=R:GOSUB 600:GOTO 100
+3900 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO POUR?":GOTO 100
+3910 GOSUB 1000:GOSUB 1100:IF H=0 THEN PRINT "THAT ISN'T HERE!":GOTO 100
+3920 IF N$="SAL" AND SA=1 THEN PRINT "THE SALT SHAKER IS EMPTY.":GOTO 100
+3930 IF N$="SAL" AND R=5 THEN SA=1:GOTO 4000
+3940 IF N$="BOT" AND FO=1 THEN PRINT "THE BOTTLE IS EMPTY.":GOTO 100
+3950 IF N$="BOT" AND R=5 THEN FO=1:GOTO 4000
+3960 PRINT "YOU CAN'T POUR THAT!":GOTO 100
+4000 PRINT "POURED!":IF SA=0 OR FO=0 THEN 100
+4010 PRINT "THERE IS AN EXPLOSION!":PRINT "EVERYTHING GOES BLACK!"
+4020 PRINT "SUDDENLY YOU ARE. . .":PRINT ". . .SOMEWHERE ELSE!"
+4030 R=6:GOSUB 700:GOTO 100
+4100 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO CLIMB?":GOTO 100
+4110 I=10:GOSUB 1100:IF N$="TRE" AND H=1 THEN 4170
+4120 I=8:GOSUB 1100:IF N$<>"LAD" OR H=0 THEN 4180
+4130 IF R<>7 THEN PRINT "WHATEVER FOR?":GOTO 100
+4150 PRINT "THE LADDER SINKS UNDER YOUR WEIGHT!"
+4160 PRINT "IT DISAPPEARS INTO THE GROUND!":OL(8)=0:GOTO 100
+4170 PRINT "YOU CAN'T REACH THE BRANCHES!":GOTO 100
+4180 PRINT "IT WON'T DO ANY GOOD.":GOTO 100
+4300 IF R=7 THEN PRINT "YOU GRAB THE LOWEST BRANCH OF THE":GOTO 4330
+4310 IF R=8 THEN PRINT "YOU GRAB A HIGHER BRANCH OF THE":GOTO 4340
+4320 PRINT "WHEE! THAT WAS FUN!":GOTO 100
+4330 PRINT "TREE AND PULL YOURSELF UP. . . .":R=8:GOTO 4350
+4340 PRINT "TREE AND PULL YOURSELF UP. . . .":R=19
+4350 GOSUB 700:GOTO 100
+4400 IF NW=1 THEN N$="GRO"
+4410 IF N$<>"GRO" AND N$<>"HOL" THEN PRINT "YOU CAN'T DIG THAT!":GOTO 100
+4420 I=9:GOSUB 1100:IF H=0 THEN PRINT "YOU DON'T HAVE A SHOVEL!":GOTO 100
+4430 IF R<>6 THEN PRINT "YOU DON'T FIND ANYTHING.":GOTO 100
+4440 IF OL(11)<>0 THEN PRINT "THERE'S NOTHING ELSE THERE!":GOTO 100
+4450 PRINT "THERE'S SOMETHING THERE!":OL(11)=6:GOSUB 600:GOTO 100
+4500 IF NW=2 AND N$<>"BOA" THEN PRINT "HOW CAN YOU ROW THAT?":GOTO 100
+4510 IF R<>13 THEN PRINT "YOU'RE NOT IN A BOAT!":GOTO 100
+4520 PRINT "YOU DON'T HAVE AN OAR!":GOTO 100
+4600 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO WAVE?":GOTO 100
+4610 IF N$<>"FAN" THEN PRINT "YOU CAN'T WAVE THAT!":GOTO 100
+4615 I=13:GOSUB 1100:IF H=0 THEN PRINT "YOU DON'T HAVE A FAN!":GOTO 100
+4620 IF R<>13 THEN PRINT "YOU FEEL A REFRESHING BREEZE!":GOTO 100
+4630 PRINT "A POWERFUL BREEZE PROPELS THE BOAT":PRINT "TO THE OPPOSITE SHORE!"
+4640 IF OL(12)=140 THEN OL(12)=142:GOTO 100
+4650 OL(12)=140:GOTO 100
+4700 IF NW=1 THEN N$="BOA"
+4702 IF R<>13 THEN 4710
+4704 IF N$<>"BOA" THEN 4720
+4706 R=OL(12) AND 127:GOSUB 700:GOTO 100
+4710 PRINT "PLEASE GIVE A DIRECTION!":GOTO 100
+4720 PRINT "HUH?":GOTO 100
+4800 IF NW=1 THEN PRINT "WHOM DO YOU WANT TO FIGHT?":GOTO 100
+4810 IF N$<>"GUA" THEN PRINT "YOU CAN'T FIGHT THEM!":GOTO 100
+4820 I=14:GOSUB 1100:IF H=0 THEN PRINT "THERE'S NO GUARD HERE!":GOTO 100
+4830 IF OL(11)<>-1 THEN PRINT "YOU DON'T HAVE A WEAPON!":GOTO 100
+4840 PRINT "THE GUARD, NOTICING YOUR SWORD,"
+4850 PRINT "WISELY RETREATS INTO THE CASTLE.":OL(14)=0:GOTO 100
+4900 IF NW=1 THEN PRINT "WHAT DO YOU WANT TO WEAR?":GOTO 100
+4910 IF N$<>"GLO" THEN PRINT "YOU CAN'T WEAR THAT!":GOTO 100
+4920 IF GL=1 THEN 4950
+4930 I=17:GOSUB 1100:IF H=0 THEN PRINT "YOU DON'T HAVE THE GLOVES.":GOTO 100
+4940 PRINT "YOU ARE NOW WEARING THE GLOVES.":GL=1:OL(17)=0:GOTO 100
+4950 PRINT "YOU ARE ALREADY WEARING THE RUBBER GLOVES.":GOTO 100
+24000 PRINT
+24010 PRINT "ALL YOUR LIFE YOU HAD HEARD THE STORIES"
+24020 PRINT "ABOUT YOUR CRAZY UNCLE SIMON. HE WAS AN"
+24030 PRINT "INVENTOR, WHO KEPT DISAPPEARING FOR"
+24040 PRINT "LONG PERIODS OF TIME, NEVER TELLING"
+24050 PRINT "ANYONE WHERE HE HAD BEEN."
+24060 PRINT
+24070 PRINT "YOU NEVER BELIEVED THE STORIES, BUT"
+24080 PRINT "WHEN YOUR UNCLE DIED AND LEFT YOU HIS"
+24090 PRINT "DIARY, YOU LEARNED THAT THEY WERE TRUE."
+24100 PRINT "YOUR UNCLE HAD DISCOVERED A MAGIC"
+24110 PRINT "LAND, AND A SECRET FORMULA THAT COULD"
+24120 PRINT "TAKE HIM THERE. IN THAT LAND WAS A"
+24130 PRINT "MAGIC RUBY, AND HIS DIARY CONTAINED"
+24140 PRINT "THE INSTRUCTIONS FOR GOING THERE TO"
+24150 PRINT "FIND IT."
+24160 PRINT
+24170 RETURN
+25000 DIM R$(NR),EX(NR,6),OB$(NO),OT$(NO),OL(NO)
+25010 FOR I=1 TO NO:READ OB$(I),OT$(I),OL(I):NEXT
+25020 FOR I=1 TO NR:READ R$(I):FOR J=1 TO 6:READ EX(I,J):NEXT J,I
+25030 RETURN
+26000 DATA "AN OLD DIARY","DIA",1
+26010 DATA "A SMALL BOX","BOX",1
+26020 DATA "A CABINET","CAB",130
+26030 DATA "A SALT SHAKER","SAL",0
+26040 DATA "A DICTIONARY","DIC",3
+26050 DATA "A WOODEN BARREL","BAR",133
+26060 DATA "A SMALL BOTTLE","BOT",0
+26070 DATA "A LADDER","LAD",4
+26080 DATA "A SHOVEL","SHO",5
+26090 DATA "A TREE","TRE",135
+26100 DATA "A GOLDEN SWORD","SWO",0
+26110 DATA "A WOODEN BOAT","BOA",140
+26120 DATA "A MAGIC FAN","FAN",8
+26130 DATA "A NASTY-LOOKING GUARD","GUA",144
+26140 DATA "A GLASS CASE","CAS",146
+26150 DATA "A GLOWING RUBY","RUB",0
+26160 DATA "A PAIR OF RUBBER GLOVES","GLO",19
+27000 DATA "IN YOUR LIVING ROOM.",4,3,2,0,0,0
+27010 DATA "IN THE KITCHEN.",0,0,0,1,0,0
+27020 DATA "IN THE LIBRARY.",1,0,0,0,0,0
+27030 DATA "IN THE FRONT YARD.",0,1,0,5,0,0
+27040 DATA "IN THE GARAGE.",0,0,4,0,0,0
+27050 DATA "IN AN OPEN FIELD.",9,7,0,0,0,0
+27060 DATA "AT THE EDGE OF A FOREST.",6,0,0,0,0,0
+27070 DATA "ON A BRANCH OF A TREE.",0,0,0,0,0,7
+27080 DATA "ON A LONG, WINDING ROAD.",0,6,10,0,0,0
+27090 DATA "ON A LONG, WINDING ROAD.",11,0,0,9,0,0
+27100 DATA "ON A LONG, WINDING ROAD.",0,10,0,12,0,0
+27110 DATA "ON THE SOUTH BANK OF A RIVER.",0,0,11,0,0,0
+27120 DATA "INSIDE THE WOODEN BOAT.",0,0,0,0,0,0
+27130 DATA "ON THE NORTH BANK OF A RIVER.",15,0,0,0,0,0
+27140 DATA "ON A WELL-TRAVELED ROAD.",16,14,0,0,0,0
+27150 DATA "IN FRONT OF A LARGE CASTLE.",128,15,0,0,0,0
+27160 DATA "IN A NARROW HALL.",0,16,0,0,18,0
+27170 DATA "IN A LARGE HALL.",0,0,0,0,0,17
+27180 DATA "ON THE TOP OF A TREE.",0,0,0,0,0,8
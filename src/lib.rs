@@ -0,0 +1,1834 @@
+//! HOW TO CREATE ADVENTURE GAMES
+//! by CHRISTOPHER LAMPTON (1986)
+//!
+//! This is a mostly faithful port of the adventure from BASIC.
+//!
+//! The game itself is a `Session`, which takes one command at a time and
+//! hands back what it printed. The `lampton` command is just one front end.
+
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::mem;
+
+// up here so the modules can use them too
+macro_rules! immobile { ($n:expr) => ($n + 128) }
+
+/// Like print!, but into the game's output for the front end to show.
+macro_rules! out {
+    ($state:expr, $($arg:tt)*) => {{
+        use std::fmt::Write;
+        write!($state.out, $($arg)*).unwrap()
+    }}
+}
+
+/// Like println!, likewise.
+macro_rules! outln {
+    ($state:expr, $($arg:tt)*) => {{
+        use std::fmt::Write;
+        writeln!($state.out, $($arg)*).unwrap()
+    }}
+}
+
+pub mod agent;
+mod basic;
+pub mod cli;
+mod diff;
+mod editor;
+mod export;
+mod ffi;
+mod import;
+mod http;
+mod inform;
+mod json;
+mod prose;
+mod server;
+mod shared;
+mod teletype;
+mod trace;
+mod tui;
+mod wrap;
+mod zcode;
+
+pub use import::import;
+
+/// These are just indices into the ROOMS array.
+/// One-based. Zero is like None. Gross, I know!
+pub type RoomId = u8;
+
+/// Inventory is a special inaccessible room.
+/// The book specifies -1 for this... but also likes using numbers larger than 127 (?)
+const INVENTORY: RoomId = 255;
+
+/// Line 20
+const MAX_INVENTORY: usize = 5;
+
+/// Where SAVE and RESTORE keep the game, in the working directory.
+const SAVE_FILE: &str = "LAMPTON.SAV";
+
+/// Touching the case this many times without gloves is fatal.
+const MAX_SHOCKS: u8 = 3;
+
+/// Static description of a room and its exits.
+struct Room {
+    desc: &'static str,
+    n: RoomId, s: RoomId, e: RoomId, w: RoomId, u: RoomId, d: RoomId
+}
+
+/// Shortcut for rooms without vertical exits.
+macro_rules! room {
+    ($desc:expr, $n:expr, $s:expr, $e:expr, $w:expr) => {
+        Room { desc: $desc, n: $n, s: $s, e: $e, w: $w, u: 0, d: 0 }
+    }
+}
+
+/// Line 25000, 27000
+static ROOMS: [Room; 20] = [
+    room!("NOWHERE?", 0, 0, 0, 0),
+    // house
+    room!("IN YOUR LIVING ROOM.", 4, 3, 2, 0), // (1)
+    room!("IN THE KITCHEN.",      0, 0, 0, 1),
+    room!("IN THE LIBRARY.",      1, 0, 0, 0),
+    room!("IN THE FRONT YARD.",   0, 1, 0, 5),
+    room!("IN THE GARAGE.",       0, 0, 4, 0),
+    // other world
+    room!("IN AN OPEN FIELD.",             9, 7, 0, 0), // (6)
+    room!("AT THE EDGE OF A FOREST.",      6, 0, 0, 0),
+    Room { desc: "ON A BRANCH OF A TREE.", n:0, s:0, e:0, w:0, u:0, d:7 },
+    room!("ON A LONG, WINDING ROAD.",      0, 6, 10, 0),
+    room!("ON A LONG, WINDING ROAD.",      11, 0, 0, 9),
+    room!("ON A LONG, WINDING ROAD.",      0, 10, 0, 12),
+    room!("ON THE SOUTH BANK OF A RIVER.", 0, 0, 11, 0), // (12)
+    room!("INSIDE THE WOODEN BOAT.",       0, 0, 0, 0),
+    room!("ON THE NORTH BANK OF A RIVER.", 15, 0, 0, 0),
+    room!("ON A WELL-TRAVELED ROAD.",      16, 14, 0, 0),
+    room!("IN FRONT OF A LARGE CASTLE.",   GUARDED, 15, 0, 0), // (16)
+    Room { desc: "IN A NARROW HALL.",      n:0, s:16, e:0, w:0, u:18, d:0  },
+    Room { desc: "IN A LARGE HALL.",       n:0, s:0,  e:0, w:0, u:0,  d:17 },
+    Room { desc: "ON THE TOP OF A TREE.",  n:0, s:0,  e:0, w:0, u:0,  d:8  },
+];
+
+const START_ROOM: RoomId = 1;
+const KITCHEN: RoomId = 2;
+const GARAGE: RoomId = 5;
+const OPEN_FIELD: RoomId = 6;
+const FOREST_EDGE: RoomId = 7;
+const TREE_BRANCH: RoomId = 8;
+const SOUTH_BANK: RoomId = 12;
+const BOAT_ROOM: RoomId = 13;
+const NORTH_BANK: RoomId = 14;
+const CASTLE_GATE: RoomId = 16;
+const NARROW_HALL: RoomId = 17;
+const LARGE_HALL: RoomId = 18;
+const TREE_TOP: RoomId = 19;
+const GUARDED: RoomId = 128;
+
+/// Static description of an in-world object.
+struct Object {
+    name: &'static str,
+    /// 3-letter noun prefix for lookup.
+    tag: &'static str,
+    /// Initial position.
+    start: RoomId
+}
+
+/// Indices into the OBJECTS array.
+/// In rust this is far more practical as a usize, so we'll forgo the bytes.
+pub type ObjId = usize;
+
+/// Line 26000
+const N_OBJECTS: ObjId = 18;
+static OBJECTS: [Object; N_OBJECTS] = [
+    Object { name: "DUMMY",                   tag: "DUM", start: 0 },
+    Object { name: "AN OLD DIARY",            tag: "DIA", start: 1 },
+    Object { name: "A SMALL BOX",             tag: "BOX", start: 1 },
+    Object { name: "A CABINET",               tag: "CAB", start: immobile!(KITCHEN) },
+    Object { name: "A SALT SHAKER",           tag: "SAL", start: 0 }, // (4)
+    Object { name: "A DICTIONARY",            tag: "DIC", start: 3 },
+    Object { name: "A WOODEN BARREL",         tag: "BAR", start: immobile!(GARAGE) },
+    Object { name: "A SMALL BOTTLE",          tag: "BOT", start: 0 },
+    Object { name: "A LADDER",                tag: "LAD", start: 4 }, // (8)
+    Object { name: "A SHOVEL",                tag: "SHO", start: 5 },
+    Object { name: "A TREE",                  tag: "TRE", start: immobile!(FOREST_EDGE) },
+    Object { name: "A GOLDEN SWORD",          tag: "SWO", start: 0 },
+    Object { name: "A WOODEN BOAT",           tag: "BOA", start: immobile!(SOUTH_BANK) },
+    Object { name: "A MAGIC FAN",             tag: "FAN", start: 8 },
+    Object { name: "A NASTY-LOOKING GUARD",   tag: "GUA", start: immobile!(CASTLE_GATE) },
+    Object { name: "A GLASS CASE",            tag: "CAS", start: immobile!(LARGE_HALL) },
+    Object { name: "A GLOWING RUBY",          tag: "RUB", start: 0 }, // (16)
+    Object { name: "A PAIR OF RUBBER GLOVES", tag: "GLO", start: 19 },
+];
+
+const SALT: ObjId = 4;
+const BOTTLE: ObjId = 7;
+const LADDER: ObjId = 8;
+const SWORD: ObjId = 11;
+const BOAT_OBJ: ObjId = 12;
+const GUARD: ObjId = 14;
+const RUBY: ObjId = 16;
+const GLOVES: ObjId = 17;
+
+/// A whole adventure: the book's own, or one imported from another listing.
+pub struct World {
+    rooms: &'static [Room],
+    objects: &'static [Object],
+    intro: &'static str,
+    /// Whether the puzzles hard-coded at lines 2500-4800 belong to this world.
+    /// Other adventures only get the generic verbs.
+    puzzles: bool,
+    /// Fixes switched back to the book's behaviour, one bit each.
+    original: u16
+}
+
+/// The book's own adventure.
+pub static STOCK: World = World {
+    rooms: &ROOMS, objects: &OBJECTS, intro: INTRO, puzzles: true, original: 0
+};
+
+/// The port's adventure as a BASIC listing, as `--faithful --export` writes it,
+/// to play beside the port. Not a transcription of the book's printed listing,
+/// so the two agree by construction wherever the exporter and interpreter work.
+const LISTING: &str = include_str!("lampton.bas");
+
+impl World {
+    /// Where YOU ARE in a room, like "IN THE KITCHEN."
+    pub fn room(&self, id: RoomId) -> &'static str {
+        self.rooms[id as usize].desc
+    }
+
+    /// How YOU CAN SEE lists an object, like "A SMALL BOX".
+    pub fn object(&self, id: ObjId) -> &'static str {
+        self.objects[id].name
+    }
+
+    fn fixed(&self, fix: Fix) -> bool {
+        self.original & fix.bit() == 0
+    }
+
+    /// This world printing its messages broken where the book broke them,
+    /// whatever the width, to set beside the book's program.
+    fn as_printed(&'static self) -> &'static World {
+        if !self.fixed(Fix::Wrap) {
+            return self;
+        }
+        Box::leak(Box::new(World { original: self.original | Fix::Wrap.bit(), ..*self }))
+    }
+}
+
+/// Where the port knowingly departs from the 1986 program.
+/// Each can be switched back with --original NAME, or all at once with --faithful.
+#[derive(Clone, Copy)]
+enum Fix { Box, Score, Daemons, Npcs, Death, Save, Restart, Replay, Hint, Wrap }
+
+impl Fix {
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+/// Each fix with its name and what the book does without it.
+static FIXES: [(Fix, &str, &str); 10] = [
+    (Fix::Box, "box", "line 3740 drops a new bottle every time the box is opened"),
+    (Fix::Score, "score", "no SCORE, and no points or rank at the end"),
+    (Fix::Daemons, "daemons", "the boat stays put and so does the guard"),
+    (Fix::Npcs, "npcs", "the guard doesn't patrol the castle or talk"),
+    (Fix::Death, "death", "no swimming, and the case is harmless"),
+    (Fix::Save, "save", "no SAVE or RESTORE"),
+    (Fix::Restart, "restart", "no RESTART"),
+    (Fix::Replay, "replay", "line 3440 asks WOULD YOU LIKE TO PLAY AGAIN?"),
+    (Fix::Hint, "hint", "no WHAT CAN I DO"),
+    (Fix::Wrap, "wrap", "lines break where the book broke them, whatever the width"),
+];
+
+/// Not in the book, which only knows whether you've won.
+/// Each milestone is worth points the first time it's reached.
+#[derive(Clone, Copy)]
+enum Milestone { OtherWorld, Sword, Guard, Case, Ruby }
+
+const MILESTONES: [Milestone; 5] = [
+    Milestone::OtherWorld, Milestone::Sword, Milestone::Guard, Milestone::Case, Milestone::Ruby
+];
+
+impl Milestone {
+    fn points(self) -> u32 {
+        use Milestone::*;
+        match self {
+            OtherWorld => 25, Sword => 15, Guard => 20, Case => 15, Ruby => 25
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+const MAX_SCORE: u32 = 100;
+
+/// Lowest score needed for each rank, best first.
+static RANKS: [(u32, &str); 5] = [
+    (MAX_SCORE, "MASTER ADVENTURER"),
+    (75, "SEASONED EXPLORER"),
+    (50, "ADVENTURER"),
+    (25, "NOVICE"),
+    (0, "BEGINNER"),
+];
+
+/// Also not in the book: something that happens by itself,
+/// some turns after being scheduled.
+struct Event {
+    /// Printed if the player is there to see it.
+    msg: &'static str,
+    obj: ObjId,
+    /// Where the object ends up.
+    dest: RoomId
+}
+
+/// Indices into the EVENTS array.
+type EventId = usize;
+
+static EVENTS: [Event; 2] = [
+    Event {
+        msg: "THE CURRENT CARRIES THE BOAT BACK|TO THE SOUTH BANK.",
+        obj: BOAT_OBJ, dest: immobile!(SOUTH_BANK)
+    },
+    Event {
+        msg: "THE GUARD RETURNS TO HIS POST.",
+        obj: GUARD, dest: immobile!(CASTLE_GATE)
+    },
+];
+
+const BOAT_DRIFTS: EventId = 0;
+const GUARD_RETURNS: EventId = 1;
+
+type Daemon = fn(&mut State);
+
+/// Run after every turn to schedule or cancel events, unless their fix is off.
+static DAEMONS: [(Fix, Daemon); 3] = [
+    (Fix::Daemons, State::boat_daemon),
+    (Fix::Daemons, State::guard_daemon),
+    (Fix::Npcs, State::npc_daemon),
+];
+
+/// A character who moves and reacts on their own.
+/// Their position is the one tracked for their object.
+struct Npc {
+    obj: ObjId,
+    /// Rooms paced in turn, once the NPC finds themselves on this route.
+    route: &'static [RoomId],
+    /// Moves between steps along the route.
+    pace: u32,
+    arrives: &'static str,
+    leaves: &'static str,
+    /// Meeting the player while they carry this gets a reaction.
+    wary_of: ObjId,
+    notices: &'static str,
+    /// Reply to TALK TO.
+    greeting: &'static str,
+    /// Replies to ASK ABOUT, by noun tag.
+    topics: &'static [(&'static str, &'static str)],
+    /// Reply to ASK ABOUT anything else.
+    shrug: &'static str
+}
+
+static NPCS: [Npc; 1] = [
+    Npc {
+        obj: GUARD,
+        route: &[NARROW_HALL, LARGE_HALL], pace: 3,
+        arrives: "THE GUARD MARCHES IN.",
+        leaves: "THE GUARD MARCHES OFF.",
+        wary_of: SWORD,
+        notices: "THE GUARD EYES YOUR SWORD NERVOUSLY.",
+        greeting: "THE GUARD GROWLS: 'NOBODY GETS INTO|THE CASTLE ON MY WATCH!'",
+        topics: &[
+            ("CAS", "'IT'S ELECTRIFIED! TOUCH IT AND|YOU'LL GET THE SHOCK OF YOUR LIFE!'"),
+            ("RUB", "'THE RUBY? IT'S SAFE IN ITS CASE,|AND THAT'S WHERE IT STAYS.'"),
+            ("SWO", "'PUT THAT THING AWAY!'"),
+        ],
+        shrug: "THE GUARD JUST GRUNTS."
+    },
+];
+
+#[derive(Clone)]
+struct State {
+    world: &'static World,
+    /// Player position.
+    here: RoomId,
+    /// Position of each object in the game.
+    positions: Vec<RoomId>,
+
+    // various flags
+    salted: bool,
+    formulated: bool,
+    gloved: bool,
+    won: bool,
+    dead: bool,
+    shocks: u8,
+
+    /// Milestones reached so far, one bit each.
+    milestones: u8,
+    /// Doubles as the turn clock.
+    moves: u32,
+    /// Pending events and the move they're due on.
+    timers: Vec<(u32, EventId)>,
+    /// Where the player was at the last tick, to spot arrivals.
+    last_here: RoomId,
+    /// Other players sharing the world, and where they are.
+    company: Vec<(String, RoomId)>,
+
+    /// Printed since the front end last took it.
+    out: String
+}
+
+impl State {
+    fn not_found(&mut self) {
+        outln!(self, "THAT ISN'T HERE!");
+    }
+
+    fn miss(&mut self, verb: &str) {
+        outln!(self, "WHAT DO YOU WANT TO {}?", verb);
+    }
+
+    fn new_game(world: &'static World) -> Self {
+        State {
+            world,
+            here: START_ROOM,
+            positions: world.objects.iter().map(|obj| obj.start).collect(),
+            salted: false, formulated: false, gloved: false,
+            won: false, dead: false, shocks: 0,
+            milestones: 0, moves: 0,
+            timers: Vec::new(),
+            last_here: START_ROOM,
+            company: Vec::new(),
+            out: String::new()
+        }
+    }
+
+    /// Plain text, one `key value...` line per field.
+    fn save(&self) -> String {
+        let positions: Vec<String> = self.positions.iter().map(|p| p.to_string()).collect();
+        let timers: Vec<String> = self.timers.iter()
+            .map(|(turn, id)| format!("{}:{}", turn, id))
+            .collect();
+        format!(
+            "here {}\npositions {}\nsalted {}\nformulated {}\ngloved {}\nshocks {}\n\
+             milestones {}\nmoves {}\ntimers {}\nlast_here {}\n",
+            self.here, positions.join(" "), self.salted, self.formulated, self.gloved,
+            self.shocks, self.milestones, self.moves, timers.join(" "), self.last_here
+        )
+    }
+
+    /// Inverse of `save`. None if anything looks off.
+    fn restore(world: &'static World, save: &str) -> Option<Self> {
+        let mut state = State::new_game(world);
+        for line in save.lines() {
+            let (key, value) = line.split_once(' ')?;
+            match key {
+                "here" => state.here = value.parse().ok()?,
+                "positions" => {
+                    state.positions = value.split_whitespace()
+                        .map(|p| p.parse().ok())
+                        .collect::<Option<_>>()?;
+                }
+                "salted" => state.salted = value.parse().ok()?,
+                "formulated" => state.formulated = value.parse().ok()?,
+                "gloved" => state.gloved = value.parse().ok()?,
+                "shocks" => state.shocks = value.parse().ok()?,
+                "milestones" => state.milestones = value.parse().ok()?,
+                "moves" => state.moves = value.parse().ok()?,
+                "timers" => {
+                    state.timers = value.split_whitespace()
+                        .map(|timer| {
+                            let (turn, id) = timer.split_once(':')?;
+                            Some((turn.parse().ok()?, id.parse().ok()?))
+                        })
+                        .collect::<Option<_>>()?;
+                }
+                "last_here" => state.last_here = value.parse().ok()?,
+                _ => return None
+            }
+        }
+        let room = |room: RoomId| (1..world.rooms.len()).contains(&(room as usize));
+        let valid = room(state.here)
+            && room(state.last_here)
+            && state.positions.len() == world.objects.len()
+            && state.positions.iter().all(|&at| at == INVENTORY || ((at & 127) as usize) < world.rooms.len())
+            && state.shocks < MAX_SHOCKS
+            && MILESTONES.iter().fold(state.milestones, |rest, m| rest & !m.bit()) == 0
+            && state.timers.iter().all(|&(_, id)| id < EVENTS.len());
+        if valid { Some(state) } else { None }
+    }
+
+    fn save_game(&mut self) {
+        match fs::write(SAVE_FILE, self.save()) {
+            Ok(()) => outln!(self, "SAVED."),
+            Err(_) => outln!(self, "SORRY, THE GAME COULDN'T BE SAVED.")
+        }
+    }
+
+    /// Swaps in the last game saved, keeping anything not yet shown.
+    fn restore_game(&mut self) -> bool {
+        let save = match fs::read_to_string(SAVE_FILE) {
+            Ok(save) => save,
+            Err(_) => {
+                outln!(self, "THERE'S NO SAVED GAME TO RESTORE.");
+                return false;
+            }
+        };
+        match State::restore(self.world, &save) {
+            Some(saved) => {
+                let out = mem::take(&mut self.out);
+                *self = saved;
+                self.out = out;
+                true
+            }
+            None => {
+                outln!(self, "THE SAVED GAME IS DAMAGED.");
+                false
+            }
+        }
+    }
+
+    /// Game over.
+    fn die(&mut self, how: &str) {
+        outln!(self, "{}", how);
+        outln!(self, "\n*** YOU HAVE DIED ***");
+        self.dead = true;
+    }
+
+    /// Called after each command. Fires any due events, then lets the daemons run.
+    fn tick(&mut self) {
+        let now = self.moves;
+        let (due, pending) = self.timers.iter().partition(|&&(turn, _)| turn <= now);
+        self.timers = pending;
+        for (_, id) in due {
+            self.fire(id);
+        }
+        if !self.world.puzzles {
+            return; // the daemons are as hard-coded as the puzzles
+        }
+        for &(fix, daemon) in DAEMONS.iter() {
+            if self.world.fixed(fix) {
+                daemon(self);
+            }
+        }
+    }
+
+    fn fire(&mut self, id: EventId) {
+        let event = &EVENTS[id];
+        let from = self.positions[event.obj] & 127;
+        if from == self.here || event.dest & 127 == self.here {
+            outln!(self, "{}", event.msg);
+        }
+        self.positions[event.obj] = event.dest;
+    }
+
+    fn schedule(&mut self, id: EventId, delay: u32) {
+        if !self.timers.iter().any(|&(_, pending)| pending == id) {
+            self.timers.push((self.moves.saturating_add(delay), id));
+        }
+    }
+
+    fn cancel(&mut self, id: EventId) {
+        self.timers.retain(|&(_, pending)| pending != id);
+    }
+
+    /// An unattended boat drifts back to the south bank.
+    fn boat_daemon(&mut self) {
+        if self.positions[BOAT_OBJ] != immobile!(NORTH_BANK) || self.anyone_in(|room| room == BOAT_ROOM) {
+            self.cancel(BOAT_DRIFTS);
+        } else {
+            self.schedule(BOAT_DRIFTS, 3);
+        }
+    }
+
+    /// The guard goes back to the gate once you wander off.
+    fn guard_daemon(&mut self) {
+        let in_castle = self.anyone_in(|room| (CASTLE_GATE..=LARGE_HALL).contains(&room));
+        if self.positions[GUARD] == immobile!(CASTLE_GATE) || in_castle {
+            self.cancel(GUARD_RETURNS);
+        } else {
+            self.schedule(GUARD_RETURNS, 2);
+        }
+    }
+
+    /// Whether the player, or anyone sharing the world, is in a room like that.
+    fn anyone_in(&self, room: impl Fn(RoomId) -> bool) -> bool {
+        room(self.here) || self.company.iter().any(|&(_, there)| room(there))
+    }
+
+        /// NPCs pace their routes and react to whoever they run into.
+    fn npc_daemon(&mut self) {
+        let arrived = self.here != self.last_here;
+        self.last_here = self.here;
+        for npc in NPCS.iter() {
+            let from = self.positions[npc.obj] & 127;
+            let mut to = from;
+            if let Some(i) = npc.route.iter().position(|&room| room == from) {
+                if self.moves.is_multiple_of(npc.pace) {
+                    to = npc.route[(i + 1) % npc.route.len()];
+                    self.positions[npc.obj] = immobile!(to);
+                    if from == self.here {
+                        outln!(self, "{}", npc.leaves);
+                    } else if to == self.here {
+                        outln!(self, "{}", npc.arrives);
+                    }
+                }
+            }
+            let met = to == self.here && (arrived || to != from);
+            if met && self.positions[npc.wary_of] == INVENTORY {
+                outln!(self, "{}", npc.notices);
+            }
+        }
+    }
+
+    /// Finds the NPC going by this tag, if they're around.
+    fn npc_here(&mut self, tag: &str) -> Option<&'static Npc> {
+        let npc = NPCS.iter().find(|npc| self.world.objects[npc.obj].tag == tag);
+        match npc {
+            Some(npc) if self.is_object_here(tag) => Some(npc),
+            Some(_) => { self.not_found(); None }
+            None if self.is_object_here(tag) => {
+                outln!(self, "THAT'S NOT VERY TALKATIVE.");
+                None
+            }
+            None => { self.not_found(); None }
+        }
+    }
+
+    fn talk(&mut self, who: &str) {
+        if let Some(npc) = self.npc_here(who) {
+            outln!(self, "{}", npc.greeting);
+        }
+    }
+
+    fn ask(&mut self, who: &str, topic: &str) {
+        if let Some(npc) = self.npc_here(who) {
+            let reply = npc.topics.iter().find(|&&(tag, _)| tag == topic);
+            outln!(self, "{}", reply.map_or(npc.shrug, |&(_, reply)| reply));
+        }
+    }
+
+    fn hint(&mut self) {
+        outln!(self, "YOU COULD TRY:");
+        let actions = actions(self);
+        for action in &actions {
+            outln!(self, "    {}", action);
+        }
+        if actions.is_empty() {
+            outln!(self, "    NOTHING, BY THE LOOKS OF IT.");
+        }
+    }
+
+    fn award(&mut self, milestone: Milestone) {
+        self.milestones |= milestone.bit();
+    }
+
+    fn score(&self) -> u32 {
+        MILESTONES.iter()
+            .filter(|m| self.milestones & m.bit() != 0)
+            .map(|m| m.points())
+            .sum()
+    }
+
+    fn rank(&self) -> &'static str {
+        let score = self.score();
+        RANKS.iter().find(|&&(min, _)| score >= min).map_or("", |&(_, rank)| rank)
+    }
+
+    fn show_score(&mut self) {
+        outln!(self, "YOU HAVE SCORED {} OUT OF {} POINTS|IN {} MOVES.", self.score(), MAX_SCORE, self.moves);
+    }
+
+    /// Once the player has won or died.
+    fn game_over(&mut self) {
+        if self.won {
+            outln!(self, "\nCONGRATULATIONS! YOU'VE WON!");
+        }
+        if self.world.fixed(Fix::Score) {
+            self.show_score();
+            if self.won {
+                outln!(self, "THAT EARNS YOU THE RANK OF|{}.", self.rank());
+            }
+        }
+    }
+
+    /// What's been printed since this was last called, laid out `width` wide,
+    /// and in modern prose if `modern`.
+    fn take_output(&mut self, width: usize, modern: bool) -> String {
+        let mut out = mem::take(&mut self.out);
+        if modern {
+            out = prose::translate(&out);
+        }
+        if self.world.fixed(Fix::Wrap) {
+            wrap::wrap(&out, width)
+        } else {
+            wrap::as_printed(&out)
+        }
+    }
+
+    fn intro(&mut self) {
+        if !self.world.intro.is_empty() {
+            outln!(self, "{}", self.world.intro);
+        }
+    }
+
+    fn look_around(&mut self) {
+        let room = &self.world.rooms[self.here as usize];
+        // 700
+        outln!(self, "\nYOU ARE {}", room.desc);
+        // 500
+        out!(self, "YOU CAN GO:");
+        for exit in self.exits() {
+            out!(self, " {}", exit);
+        }
+        self.list_items_here();
+    }
+
+    /// The ways out of here, in the order line 500 lists them.
+    fn exits(&self) -> Vec<&'static str> {
+        let room = &self.world.rooms[self.here as usize];
+        // directions are put in an array at 25010
+        [(room.n, "NORTH"), (room.s, "SOUTH"), (room.e, "EAST"),
+         (room.w, "WEST"), (room.d, "DOWN"), (room.u, "UP")]
+            .iter()
+            .filter(|&&(dest, _)| dest > 0)
+            .map(|&(_, name)| name)
+            .collect()
+    }
+
+    fn list_items_here(&mut self) {
+        // 600
+        outln!(self, "\nYOU CAN SEE:");
+        let mut found = false;
+        for (obj, &room) in self.world.objects.iter().zip(&self.positions) {
+            if room & 127 == self.here {
+                outln!(self, "    {}", obj.name);
+                found = true;
+            }
+        }
+        for (name, room) in &self.company {
+            if *room == self.here {
+                outln!(self, "    {}", name);
+                found = true;
+            }
+        }
+        if !found {
+            outln!(self, "    THERE IS NOTHING OF INTEREST HERE.");
+        }
+    }
+
+    /// Line 2000.
+    fn go(&mut self, direction: Dir) {
+        use Dir::*;
+        let room = &self.world.rooms[self.here as usize];
+        let dest = match direction {
+            N => room.n, S => room.s, E => room.e,
+            W => room.w, U => room.u, D => room.d,
+            // the boat might have drifted off
+            Boat if self.world.puzzles && self.positions[BOAT_OBJ] == immobile!(self.here) => {
+                BOAT_ROOM
+            }
+            Boat => 0
+        };
+        if dest > 0 && (dest as usize) < self.world.rooms.len() {
+            self.here = dest;
+            self.look_around();
+        } else if dest == GUARDED && self.world.puzzles { // Line 2410
+            if self.positions[GUARD] == immobile!(CASTLE_GATE) {
+                outln!(self, "THE GUARD WON'T LET YOU!");
+            } else {
+                self.here = NARROW_HALL;
+                self.look_around();
+            }
+        } else {
+            outln!(self, "YOU CAN'T GO THERE!");
+        }
+    }
+
+    fn inventory(&mut self) {
+        if self.gloved {
+            outln!(self, "YOU ARE WEARING RUBBER GLOVES.");
+        }
+        outln!(self, "YOU ARE CARRYING:");
+        let mut found = false;
+        for (obj, &room) in self.world.objects.iter().zip(&self.positions) {
+            if room == INVENTORY {
+                outln!(self, "    {}", obj.name);
+                found = true;
+            }
+        }
+        if !found {
+            outln!(self, "    NOTHING");
+        }
+    }
+
+    fn inventory_count(&self) -> usize {
+        self.positions.iter().filter(|&room| *room == INVENTORY).count()
+    }
+
+    /// Returns info about an object given the first 3 letters of its name.
+    /// Line 1000
+    fn lookup_object(&self, tag: &str) -> Option<(ObjId, RoomId)> {
+        self.world.objects.iter().position(|obj| obj.tag == tag)
+            .filter(|&id| id != 0) // the loop at 1000 starts at 1
+            .map(|id| (id, self.positions[id]))
+    }
+
+    /// Is the object in this room or in your inventory?
+    fn is_object_here(&self, tag: &str) -> bool {
+        if let Some((_, room)) = self.lookup_object(tag) {
+            room == INVENTORY || room & 127 == self.here
+        } else {
+            false
+        }
+    }
+
+    fn pick_up(&mut self, tag: &str) {
+        if let Some((id, room)) = self.lookup_object(tag) { // Line 2520
+            if room == INVENTORY { // 2530
+                outln!(self, "YOU ALREADY HAVE IT!");
+            } else if room > 127 { // 2540
+                outln!(self, "YOU CAN'T GET THAT!");
+            } else if room != self.here { // 2550
+                self.not_found();
+            } else if self.inventory_count() >= MAX_INVENTORY { // 2570
+                outln!(self, "YOU CAN'T CARRY ANY MORE.");
+            } else if tag == "RUB" && self.world.puzzles { // 2575
+                self.award(Milestone::Ruby);
+                self.won = true;
+            } else { // 2580
+                self.positions[id] = INVENTORY;
+                outln!(self, "TAKEN.");
+            }
+        } else {
+            outln!(self, "YOU CAN'T GET THAT!");
+        }
+    }
+
+    fn drop(&mut self, tag: &str) {
+        if let Some((id, room)) = self.lookup_object(tag) {
+            if room == INVENTORY {
+                self.positions[id] = self.here;
+                outln!(self, "DROPPED.");
+                return; // early return!
+            }
+        }
+        outln!(self, "YOU DON'T HAVE THAT!");
+    }
+
+    /// Line 2900
+    fn examine(&mut self, tag: &str) {
+        if tag == "GRO" && self.world.puzzles { // 2910
+            if self.here != OPEN_FIELD { // 2920
+                outln!(self, "IT LOOKS LIKE GROUND!");
+            } else if self.positions[SWORD] == 0 {
+                outln!(self, "IT LOOKS LIKE SOMETHING'S BURIED HERE.");
+            } else {
+                outln!(self, "THERE'S A HOLE HERE.");
+            }
+        } else if self.is_object_here(tag) {
+            outln!(self, "{}", match tag {
+                "BOT" if self.world.puzzles => "THERE'S SOMETHING WRITTEN ON IT!", // 3020
+                "CAS" if self.world.puzzles => "THERE'S A JEWEL INSIDE!", // 3030
+                "BAR" if self.world.puzzles => "IT'S FILLED WITH RAINWATER.",
+                _ => "YOU SEE NOTHING UNUSUAL."
+            });
+        } else {
+            self.not_found();
+        }
+    }
+
+    fn open(&mut self, tag: &str) {
+        if !self.is_object_here(tag) {
+            self.not_found();
+        } else if tag == "BOX" { // 3740
+            // don't let the box infinitely respawn the bottle
+            if self.positions[BOTTLE] == 0 || !self.world.fixed(Fix::Box) {
+                self.positions[BOTTLE] = self.here;
+                outln!(self, "SOMETHING FELL OUT!");
+                self.list_items_here();
+            } else {
+                outln!(self, "THE BOX IS ALREADY OPEN.");
+            }
+        } else if tag == "CAB" {
+            if self.positions[SALT] == 0 {
+                self.positions[SALT] = self.here;
+                outln!(self, "THERE'S SOMETHING INSIDE!");
+                self.list_items_here();
+            } else {
+                outln!(self, "THE CABINET IS ALREADY OPEN.");
+            }
+        } else if tag == "CAS" {
+            if self.positions[RUBY] != 0 {
+                outln!(self, "THE CASE IS ALREADY OPEN.");
+            } else if self.gloved {
+                outln!(self, "THE GLOVES INSULATE AGAINST THE|ELECTRICITY! THE CASE OPENS!");
+                self.positions[RUBY] = self.here;
+                self.award(Milestone::Case);
+                self.list_items_here();
+            } else {
+                outln!(self, "THE CASE IS ELECTRIFIED!");
+                if !self.world.fixed(Fix::Death) {
+                    return;
+                }
+                self.shocks += 1;
+                if self.shocks >= MAX_SHOCKS {
+                    self.die("THE SHOCK STOPS YOUR HEART!");
+                } else if self.shocks + 1 == MAX_SHOCKS {
+                    outln!(self, "ONE MORE JOLT LIKE THAT COULD KILL YOU.");
+                }
+            }
+        } else {
+            outln!(self, "YOU CAN'T OPEN THAT!");
+        }
+    }
+
+    /// Line 3500
+    fn read(&mut self, tag: &str) {
+        if !self.is_object_here(tag) {
+            self.not_found();
+        } else if tag == "DIA" {
+            outln!(self, "IT SAYS: 'ADD SODIUM CHLORIDE PLUS THE|FORMULA TO RAINWATER, TO REACH THE|OTHER WORLD.'");
+        } else if tag == "DIC" {
+            outln!(self, "IT SAYS: SODIUM CHLORIDE IS|COMMON TABLE SALT.");
+        } else if tag == "BOT" {
+            outln!(self, "IT READS: 'SECRET FORMULA'.");
+        } else {
+            outln!(self, "YOU CAN'T READ THAT!");
+        }
+    }
+
+    /// Line 3900
+    fn pour(&mut self, tag: &str) {
+        match tag {
+            _ if !self.is_object_here(tag) => self.not_found(),
+            "SAL" if self.salted => outln!(self, "THE SALT SHAKER IS EMPTY."),
+            "SAL" if self.here == GARAGE => {
+                self.salted = true;
+                self.poured_into_barrel();
+            }
+            "BOT" if self.formulated => outln!(self, "THE BOTTLE IS EMPTY."),
+            "BOT" if self.here == GARAGE => {
+                self.formulated = true;
+                self.poured_into_barrel();
+            }
+            _ => outln!(self, "YOU CAN'T POUR THAT!")
+        }
+    }
+
+    fn poured_into_barrel(&mut self) {
+        outln!(self, "POURED!");
+        if self.salted && self.formulated { // Line 4010
+            outln!(self, "THERE IS AN EXPLOSION!");
+            outln!(self, "EVERYTHING GOES BLACK!");
+            outln!(self, "SUDDENLY YOU ARE. . .");
+            outln!(self, ". . .SOMEWHERE ELSE!");
+            self.here = OPEN_FIELD;
+            self.award(Milestone::OtherWorld);
+            self.look_around();
+        }
+    }
+
+    /// Line 4100
+    fn climb(&mut self, tag: &str) {
+        if tag == "TRE" && self.is_object_here("TRE") {
+            outln!(self, "YOU CAN'T REACH THE BRANCHES!");
+        } else if tag == "LAD" && self.is_object_here("LAD") {
+            if self.here == FOREST_EDGE { // Line 4150
+                outln!(self, "THE LADDER SINKS UNDER YOUR WEIGHT!|IT DISAPPEARS INTO THE GROUND!");
+                self.positions[LADDER] = 0;
+            } else {
+                outln!(self, "WHATEVER FOR?");
+            }
+        } else {
+            outln!(self, "IT WON'T DO ANY GOOD.");
+        }
+    }
+
+    /// Line 4300
+    fn jump(&mut self) {
+        if self.here == FOREST_EDGE {
+            outln!(self, "YOU GRAB THE LOWEST BRANCH OF THE|TREE AND PULL YOURSELF UP. . . .");
+            self.here = TREE_BRANCH;
+            self.look_around();
+        } else if self.here == TREE_BRANCH {
+            outln!(self, "YOU GRAB A HIGHER BRANCH OF THE|TREE AND PULL YOURSELF UP. . . .");
+            self.here = TREE_TOP;
+            self.look_around();
+        } else {
+            outln!(self, "WHEE! THAT WAS FUN!");
+        }
+    }
+
+    /// Line 4400
+    fn dig(&mut self, obj: &str) {
+        if obj != "GRO" && obj != "HOL" {
+            outln!(self, "YOU CAN'T DIG THAT!");
+        } else if !self.is_object_here("SHO") {
+            outln!(self, "YOU DON'T HAVE A SHOVEL!");
+        } else if self.here != OPEN_FIELD {
+            outln!(self, "YOU DON'T FIND ANYTHING.");
+        } else if self.positions[SWORD] != 0 {
+            outln!(self, "THERE'S NOTHING ELSE THERE!");
+        } else {
+            outln!(self, "THERE'S SOMETHING THERE!");
+            self.positions[SWORD] = OPEN_FIELD;
+            self.award(Milestone::Sword);
+            self.list_items_here();
+        }
+    }
+
+    /// Line 4500
+    fn row_boat(&mut self) {
+        if self.here != BOAT_ROOM {
+            outln!(self, "YOU'RE NOT IN A BOAT!");
+        } else {
+            outln!(self, "YOU DON'T HAVE AN OAR!");
+        }
+    }
+
+    fn swim(&mut self) {
+        if self.here == SOUTH_BANK || self.here == NORTH_BANK || self.here == BOAT_ROOM {
+            outln!(self, "YOU PLUNGE INTO THE RIVER...");
+            self.die("THE CURRENT DRAGS YOU UNDER!");
+        } else {
+            outln!(self, "THERE'S NOWHERE TO SWIM HERE.");
+        }
+    }
+
+    /// Line 4600
+    fn wave(&mut self, obj: &str) {
+        if obj != "FAN" { // 4610
+            outln!(self, "YOU CAN'T WAVE THAT!");
+        } else if !self.is_object_here("FAN") { // 4615
+            outln!(self, "YOU DON'T HAVE A FAN!");
+        } else if self.here != BOAT_ROOM { // 4620
+            outln!(self, "YOU FEEL A REFRESHING BREEZE!");
+        } else { // 4630
+            outln!(self, "A POWERFUL BREEZE PROPELS THE BOAT|TO THE OPPOSITE SHORE!");
+            if self.positions[BOAT_OBJ] == immobile!(SOUTH_BANK) {
+                self.positions[BOAT_OBJ] = immobile!(NORTH_BANK);
+            } else {
+                self.positions[BOAT_OBJ] = immobile!(SOUTH_BANK);
+            }
+        }
+    }
+
+    /// Line 4700
+    fn leave(&mut self, obj: &str) {
+        if self.here == BOAT_ROOM {
+            if obj == "BOA" {
+                self.here = self.positions[BOAT_OBJ] & 127;
+                self.look_around();
+            } else {
+                outln!(self, "HUH?"); // 4720
+            }
+        } else {
+            outln!(self, "PLEASE GIVE A DIRECTION!"); // 4710
+        }
+    }
+
+    /// Line 4800
+    fn fight_guard(&mut self) {
+        if !self.is_object_here("GUA") {
+            outln!(self, "THERE'S NO GUARD HERE!");
+        } else if self.positions[SWORD] != INVENTORY {
+            outln!(self, "YOU DON'T HAVE A WEAPON!");
+        } else {
+            outln!(self, "THE GUARD, NOTICING YOUR SWORD,|WISELY RETREATS INTO THE CASTLE.");
+            if self.world.fixed(Fix::Npcs) {
+                // from now on he paces the halls
+                let hall = if self.here == LARGE_HALL { NARROW_HALL } else { LARGE_HALL };
+                self.positions[GUARD] = immobile!(hall);
+            } else {
+                self.positions[GUARD] = 0; // 4850
+            }
+            self.award(Milestone::Guard);
+        }
+    }
+
+    fn wear_gloves(&mut self) {
+        if self.gloved {
+            outln!(self, "YOU ARE ALREADY WEARING THE RUBBER GLOVES.");
+        } else if !self.is_object_here("GLO") {
+            outln!(self, "YOU DON'T HAVE THE GLOVES.");
+        } else {
+            outln!(self, "YOU ARE NOW WEARING THE GLOVES.");
+            self.gloved = true;
+            self.positions[GLOVES] = 0;
+        }
+    }
+}
+
+
+enum Dir { N, S, E, W, U, D, Boat }
+
+impl Dir {
+    fn parse(tag: &str) -> Option<Self> {
+        use Dir::*;
+        Some(match tag {
+            "N" | "NOR" => N,
+            "S" | "SOU" => S,
+            "E" | "EAS" => E,
+            "W" | "WES" => W,
+            "U" | "UP"  => U,
+            "D" | "DOW" => D,
+            "BOA"       => Boat,
+            _           => return None
+        })
+    }
+}
+
+/// What the main loop should do after a command.
+enum Outcome {
+    Continue,
+    /// Nothing happened in the game, so no time passes.
+    Untimed,
+    Quit,
+    Restart
+}
+
+/// The words of a command as the parser sees them.
+fn words(input: &str) -> Vec<&str> {
+    // Mr. Lampton specified to only use the first 3 letters of each word.
+    // Letters, not bytes: anything can be typed.
+    let mut tags: Vec<&str> = input
+        .split_ascii_whitespace()
+        .map(|w| w.char_indices().nth(3).map_or(w, |(end, _)| &w[..end]))
+        .collect();
+    if tags.len() > 1 {
+        tags[1] = match tags[1] {
+            "SHA" => "SAL", // Line 210
+            "FOR" => "BOT", // 220
+            noun => noun
+        };
+    }
+    tags
+}
+
+/// Line 100
+fn parser(input: &str, state: &mut State) -> Outcome {
+    let tags = words(input);
+    // the commands about the game itself take no time
+    match AsRef::<[&str]>::as_ref(&tags) {
+        [] => {}
+        ["Q"] | ["QUI"] => return Outcome::Quit,
+        ["RES"] if input.starts_with("RESTA") && state.world.fixed(Fix::Restart) => {
+            return Outcome::Restart;
+        }
+        ["SCO"] if state.world.fixed(Fix::Score) => state.show_score(),
+        ["SAV"] if state.world.fixed(Fix::Save) => state.save_game(),
+        ["RES"] if input.starts_with("RESTO") && state.world.fixed(Fix::Save) => {
+            if state.restore_game() {
+                state.look_around();
+            }
+        }
+        ["WHA", "CAN", "I", "DO"] | ["HIN"] if state.world.fixed(Fix::Hint) => state.hint(),
+        _ => {
+            let outcome = act(&tags, state);
+            if !matches!(outcome, Outcome::Untimed) {
+                state.moves = state.moves.saturating_add(1);
+            }
+            return outcome;
+        }
+    }
+    Outcome::Untimed
+}
+
+/// The I DON'T KNOW HOW TO DO THAT that ends the parser's lines, which takes no time.
+fn unknown(state: &mut State) -> Outcome {
+    outln!(state, "I DON'T KNOW HOW TO DO THAT.");
+    Outcome::Untimed
+}
+
+/// A command in the game, as one move.
+fn act(tags: &[&str], state: &mut State) -> Outcome {
+    // Are we going somewhere? ("GO" verb optional)
+    let dir_tag = if tags.len() > 1 && tags[0] == "GO" { tags[1] } else { tags[0] };
+    if let Some(dir) = Dir::parse(dir_tag) {
+        state.go(dir);
+        return Outcome::Continue;
+    }
+    match tags {
+        ["I"] | ["INV"] => state.inventory(),
+        ["L"] | ["LOO"] => state.look_around(),
+        ["GO"] => outln!(state, "GO WHERE?"),
+
+        ["EXA"] => state.miss("EXAMINE"),
+        ["EXA", obj] | ["LOO", obj] => state.examine(obj),
+
+        ["GET"] | ["TAK"] => state.miss("GET"),
+        ["GET", item] | ["TAK", item] => state.pick_up(item),
+
+        ["DRO"] => state.miss("DROP"),  ["DRO", item] => state.drop(item),
+
+        // everything else is specific to the book's adventure
+        _ if !state.world.puzzles => return unknown(state),
+
+        ["OPE"] => state.miss("OPEN"),  ["OPE", obj] => state.open(obj),
+        ["REA"] => state.miss("READ"),  ["REA", obj] => state.read(obj),
+        ["POU"] => state.miss("POUR"),  ["POU", obj] => state.pour(obj),
+        ["CLI"] => state.miss("CLIMB"), ["CLI", obj] => state.climb(obj),
+        ["WAV"] => state.miss("WAVE"),  ["WAV", obj] => state.wave(obj),
+
+        ["JUM"] | ["JUM", _] => state.jump(),
+        ["DIG"] => state.dig("GRO"), ["DIG", obj] => state.dig(obj),
+        ["SWI"] | ["SWI", _] if state.world.fixed(Fix::Death) => state.swim(),
+        ["ROW"] | ["ROW", "BOA"] => state.row_boat(),
+        ["ROW", _] => outln!(state, "HOW CAN YOU ROW THAT?"),
+        ["LEA"] | ["EXI"] => state.leave("BOA"),
+        ["LEA", obj] | ["EXI", obj] => state.leave(obj),
+
+        ["FIG"] => outln!(state, "WHOM DO YOU WANT TO FIGHT?"),
+        ["FIG", "GUA"] => state.fight_guard(),
+        ["FIG", _] => outln!(state, "YOU CAN'T FIGHT THEM!"),
+
+        _ if !state.world.fixed(Fix::Npcs) && ["TAL", "ASK"].contains(&tags[0]) => return unknown(state),
+        ["TAL"] | ["TAL", "TO"] => outln!(state, "WHOM DO YOU WANT TO TALK TO?"),
+        ["TAL", "TO", who] | ["TAL", who] => state.talk(who),
+        ["ASK"] => outln!(state, "WHOM DO YOU WANT TO ASK?"),
+        ["ASK", _] => outln!(state, "WHAT DO YOU WANT TO ASK ABOUT?"),
+        ["ASK", who, "ABO", topic] | ["ASK", who, topic] => state.ask(who, topic),
+
+        ["WEA"] => state.miss("WEAR"),
+        ["WEA", "GLO"] => state.wear_gloves(),
+        ["WEA", _] => outln!(state, "YOU CAN'T WEAR THAT!"),
+
+        _ => return unknown(state)
+    }
+    Outcome::Continue
+}
+
+/// Every command `parser` would act on just now, with the nouns in scope.
+/// Not the ones about the game itself, like SAVE or QUIT.
+fn commands(state: &State) -> Vec<String> {
+    let world = state.world;
+    let mut commands: Vec<String> = state.exits().iter().map(|exit| exit.to_string()).collect();
+    if world.puzzles && state.positions[BOAT_OBJ] == immobile!(state.here) {
+        commands.push("GO BOAT".to_string());
+    }
+    commands.push("INVENTORY".to_string());
+    commands.push("LOOK".to_string());
+    let in_scope = (1..world.objects.len()).filter(|&id| state.is_object_here(world.objects[id].tag));
+    for id in in_scope {
+        let noun = noun(&world.objects[id]);
+        let take = if state.positions[id] == INVENTORY { "DROP" } else { "GET" };
+        commands.push(format!("{} {}", take, noun));
+        commands.push(format!("EXAMINE {}", noun));
+        if world.puzzles {
+            for verb in ["OPEN", "READ", "POUR", "CLIMB", "WAVE", "FIGHT", "WEAR"].iter() {
+                commands.push(format!("{} {}", verb, noun));
+            }
+        }
+    }
+    if !world.puzzles {
+        return commands;
+    }
+    commands.push("JUMP".to_string());
+    commands.push("DIG".to_string());
+    commands.push("ROW".to_string());
+    if state.here == BOAT_ROOM {
+        commands.push("LEAVE BOAT".to_string());
+    }
+    if world.fixed(Fix::Death) {
+        commands.push("SWIM".to_string());
+    }
+    if world.fixed(Fix::Npcs) {
+        for npc in NPCS.iter().filter(|npc| state.is_object_here(world.objects[npc.obj].tag)) {
+            let who = noun(&world.objects[npc.obj]);
+            commands.push(format!("TALK TO {}", who));
+            for &(tag, _) in npc.topics.iter() {
+                let topic = world.objects.iter().find(|obj| obj.tag == tag).map_or(tag, noun);
+                commands.push(format!("ASK {} ABOUT {}", who, topic));
+            }
+        }
+    }
+    commands
+}
+
+/// The commands from `commands` that would change something, found by
+/// trying each on a copy of the game. The time they take doesn't count.
+fn actions(state: &State) -> Vec<String> {
+    let before = state.save();
+    commands(state).into_iter()
+        .filter(|command| {
+            let mut tried = state.clone();
+            parser(command, &mut tried);
+            tried.moves = state.moves;
+            tried.won || tried.dead || tried.save() != before
+        })
+        .collect()
+}
+
+/// The word in an object's name that the parser knows it by, like DIARY for DIA.
+fn noun(obj: &Object) -> &'static str {
+    obj.name.split(' ').find(|word| word.starts_with(obj.tag)).unwrap_or(obj.tag)
+}
+
+/// Two paragraphs, broken where the book broke them.
+const INTRO: &str = "
+ALL YOUR LIFE YOU HAD HEARD THE STORIES|\
+ABOUT YOUR CRAZY UNCLE SIMON. HE WAS AN|\
+INVENTOR, WHO KEPT DISAPPEARING FOR|\
+LONG PERIODS OF TIME, NEVER TELLING|\
+ANYONE WHERE HE HAD BEEN.
+
+YOU NEVER BELIEVED THE STORIES, BUT|\
+WHEN YOUR UNCLE DIED AND LEFT YOU HIS|\
+DIARY, YOU LEARNED THAT THEY WERE TRUE.|\
+YOUR UNCLE HAD DISCOVERED A MAGIC|\
+LAND, AND A SECRET FORMULA THAT COULD|\
+TAKE HIM THERE. IN THAT LAND WAS A|\
+MAGIC RUBY, AND HIS DIARY CONTAINED|\
+THE INSTRUCTIONS FOR GOING THERE TO|\
+FIND IT.
+";
+
+/// Where a game's input comes from and its output goes.
+trait Terminal {
+    fn print(&mut self, text: &str) -> io::Result<()>;
+    /// One line of input, without the line break. None at the end of input.
+    fn read_line(&mut self) -> io::Result<Option<String>>;
+    /// Called by the BASIC interpreter as it starts running each line.
+    fn trace(&mut self, _line: u32) {}
+    /// Told what's worth typing at the next prompt, to complete half-typed words.
+    fn offer(&mut self, _commands: &[String]) {}
+}
+
+/// The plain WHAT NOW? prompt on stdin and stdout.
+struct Stdio;
+
+impl Terminal for Stdio {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        stdout.write_all(text.as_bytes())?;
+        stdout.flush()
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(input.trim_end_matches(&['\r', '\n'][..]).to_string()))
+    }
+}
+
+/// What the game is waiting to hear.
+#[derive(Clone, Copy, PartialEq)]
+enum Question {
+    Command,
+    /// Line 3410, before quitting or restarting.
+    SureQuit,
+    SureRestart,
+    /// Line 3440
+    PlayAgain,
+    /// Ours, with RESTORE.
+    AfterEnd,
+    Over
+}
+
+impl Question {
+    /// How it's asked in `world`, in modern prose if `modern`.
+    fn prompt(self, world: &World, modern: bool) -> &'static str {
+        let prompt = match self {
+            Question::Command => "\nWHAT NOW? ",
+            Question::SureQuit | Question::SureRestart => "ARE YOU SURE? ",
+            Question::PlayAgain => "\nWOULD YOU LIKE TO PLAY AGAIN? ",
+            Question::AfterEnd if world.fixed(Fix::Save) => "\nWOULD YOU LIKE TO RESTART, RESTORE\nOR QUIT? ",
+            Question::AfterEnd => "\nWOULD YOU LIKE TO RESTART OR QUIT? ",
+            Question::Over => ""
+        };
+        match prose::exact(prompt) {
+            Some(text) if modern => text,
+            _ => prompt
+        }
+    }
+}
+
+/// One game of an adventure, played a command at a time.
+pub struct Session {
+    state: State,
+    /// Whether to ask ARE YOU SURE? before quitting or restarting.
+    confirm: bool,
+    question: Question,
+    /// Columns to wrap the game's text to.
+    width: usize,
+    /// Whether it's in modern prose rather than the book's capitals.
+    modern: bool
+}
+
+impl Session {
+    /// Starts a game. Its introduction is waiting in `take_output`.
+    pub fn new(world: &'static World, confirm: bool) -> Self {
+        let mut session = Session {
+            state: State::new_game(world), confirm, question: Question::Command, width: wrap::BOOK_WIDTH, modern: false
+        };
+        session.state.intro();
+        session.state.look_around();
+        session
+    }
+
+    /// Answers whatever `prompt` asked, and returns what the game printed.
+    pub fn step(&mut self, input: &str) -> String {
+        let input = input.trim().to_ascii_uppercase();
+        match self.question {
+            Question::Command => self.command(&input),
+            Question::SureQuit if input.starts_with('Y') => self.question = Question::Over,
+            Question::SureRestart if input.starts_with('Y') => self.restart(),
+            Question::SureQuit | Question::SureRestart => self.question = Question::Command,
+            Question::PlayAgain if input.starts_with('Y') => self.restart(),
+            Question::PlayAgain => self.question = Question::Over,
+            Question::AfterEnd => match input.as_str() {
+                "Q" | "QUIT" => self.question = Question::Over,
+                "RESTART" => self.restart(),
+                "RESTORE" if self.state.world.fixed(Fix::Save) && self.state.restore_game() => {
+                    self.question = Question::Command;
+                    self.state.look_around();
+                }
+                _ => {} // asks again
+            }
+            Question::Over => {}
+        }
+        self.take_output()
+    }
+
+    fn command(&mut self, command: &str) {
+        if command.is_empty() {
+            return; // not worth a turn
+        }
+        match parser(command, &mut self.state) {
+            Outcome::Continue => self.state.tick(),
+            Outcome::Untimed => {}
+            Outcome::Quit if self.confirm => self.question = Question::SureQuit,
+            Outcome::Quit => self.question = Question::Over,
+            Outcome::Restart if self.confirm => self.question = Question::SureRestart,
+            Outcome::Restart => self.restart()
+        }
+        if self.state.won || self.state.dead {
+            self.end();
+        }
+    }
+
+    fn restart(&mut self) {
+        let out = mem::take(&mut self.state.out);
+        self.state = State::new_game(self.state.world);
+        self.state.out = out;
+        self.state.intro();
+        self.state.look_around();
+        self.question = Question::Command;
+    }
+
+    fn end(&mut self) {
+        self.state.game_over();
+        self.question = if self.state.world.fixed(Fix::Replay) { Question::AfterEnd } else { Question::PlayAgain };
+    }
+
+    /// What the game has printed since this was last called.
+    pub fn take_output(&mut self) -> String {
+        self.state.take_output(self.width, self.modern)
+    }
+
+    /// Wraps what the game prints from now on to `width` columns, rather than the book's 40.
+    pub fn set_width(&mut self, width: usize) {
+        self.width = width;
+    }
+
+    /// Puts what the game prints from now on in modern prose, or back in the book's capitals.
+    pub fn set_modern(&mut self, modern: bool) {
+        self.modern = modern;
+    }
+
+    /// What to show before reading the next line.
+    pub fn prompt(&self) -> &'static str {
+        self.question.prompt(self.state.world, self.modern)
+    }
+
+    /// Whether the player has quit, or declined to play again.
+    pub fn is_over(&self) -> bool {
+        self.question == Question::Over
+    }
+
+    /// The game so far as text, for `restore`.
+    pub fn save(&self) -> String {
+        self.state.save()
+    }
+
+    /// Picks up a game from `save`. False, with the game untouched, if it can't.
+    pub fn restore(&mut self, save: &str) -> bool {
+        match State::restore(self.state.world, save) {
+            Some(state) => {
+                self.state = State { out: mem::take(&mut self.state.out), ..state };
+                self.question = Question::Command;
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn world(&self) -> &'static World {
+        self.state.world
+    }
+
+    /// The player's room.
+    pub fn here(&self) -> RoomId {
+        self.state.here
+    }
+
+    /// The directions out of the player's room, as YOU CAN GO lists them.
+    pub fn exits(&self) -> Vec<&'static str> {
+        self.state.exits()
+    }
+
+    /// Objects in the player's room, including immobile ones.
+    pub fn objects_here(&self) -> Vec<ObjId> {
+        self.objects_at(|room| room & 127 == self.state.here)
+    }
+
+    pub fn inventory(&self) -> Vec<ObjId> {
+        self.objects_at(|room| room == INVENTORY)
+    }
+
+    fn objects_at(&self, at: impl Fn(RoomId) -> bool) -> Vec<ObjId> {
+        // object 0 is the dummy
+        (1..self.state.positions.len()).filter(|&id| at(self.state.positions[id])).collect()
+    }
+
+    pub fn score(&self) -> u32 {
+        self.state.score()
+    }
+
+    pub fn moves(&self) -> u32 {
+        self.state.moves
+    }
+
+    /// Commands the parser would act on now, with the nouns in scope.
+    /// Not all of them will do anything.
+    pub fn commands(&self) -> Vec<String> {
+        commands(&self.state)
+    }
+
+    /// Everything worth typing at the prompt: the commands in scope,
+    /// the ones about the game itself, or the answers to a question.
+    pub fn completions(&self) -> Vec<String> {
+        let world = self.state.world;
+        let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect();
+        match self.question {
+            Question::Command => {
+                let about_the_game = [
+                    (Fix::Score, "SCORE"), (Fix::Save, "SAVE"), (Fix::Save, "RESTORE"),
+                    (Fix::Restart, "RESTART"), (Fix::Hint, "HINT"),
+                ];
+                let mut commands = self.commands();
+                commands.extend(about_the_game.iter()
+                    .filter(|&&(fix, _)| world.fixed(fix))
+                    .map(|&(_, command)| command.to_string()));
+                commands.push("QUIT".to_string());
+                commands
+            }
+            Question::SureQuit | Question::SureRestart | Question::PlayAgain => words(&["YES", "NO"]),
+            Question::AfterEnd if world.fixed(Fix::Save) => words(&["RESTART", "RESTORE", "QUIT"]),
+            Question::AfterEnd => words(&["RESTART", "QUIT"]),
+            Question::Over => Vec::new()
+        }
+    }
+
+    /// The commands from `commands` that would change something.
+    pub fn actions(&self) -> Vec<String> {
+        actions(&self.state)
+    }
+
+    pub fn won(&self) -> bool {
+        self.state.won
+    }
+
+    pub fn dead(&self) -> bool {
+        self.state.dead
+    }
+}
+
+/// Plays a session on a terminal until the player quits or the input runs out,
+/// wrapping its text to `width` columns, and in modern prose if `modern`.
+fn play(term: &mut dyn Terminal, world: &'static World, confirm: bool, width: usize, modern: bool) -> io::Result<()> {
+    let mut session = Session::new(world, confirm);
+    session.set_width(width);
+    session.set_modern(modern);
+    term.print(&session.take_output())?;
+    while !session.is_over() {
+        term.print(session.prompt())?;
+        term.offer(&session.completions());
+        match term.read_line()? {
+            Some(input) => term.print(&session.step(&input))?,
+            None => break
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A terminal that types its lines and keeps what's printed.
+    pub(crate) struct Typist {
+        lines: Vec<&'static str>,
+        pub(crate) printed: String
+    }
+
+    impl Typist {
+        pub(crate) fn new(lines: &[&'static str]) -> Self {
+            Typist { lines: lines.to_vec(), printed: String::new() }
+        }
+    }
+
+    impl Terminal for Typist {
+        fn print(&mut self, text: &str) -> io::Result<()> {
+            self.printed += text;
+            Ok(())
+        }
+
+        fn read_line(&mut self) -> io::Result<Option<String>> {
+            Ok(if self.lines.is_empty() { None } else { Some(self.lines.remove(0).to_string()) })
+        }
+    }
+
+    /// From the living room to the ruby by the shortest way, checking the score on the way.
+    pub(crate) const WALKTHROUGH: [&str; 39] = [
+        "OPEN BOX", "GET BOTTLE", "E", "OPEN CABINET", "GET SALT", "W", "N", "W", "GET SHOVEL",
+        "POUR SALT", "SCORE", "POUR BOTTLE", "DROP SALT", "DROP BOTTLE", "DIG", "GET SWORD", "S",
+        "JUMP", "GET FAN", "JUMP", "GET GLOVES", "D", "D", "N", "N", "E", "N", "W", "BOAT",
+        "WAVE FAN", "LEAVE BOAT", "N", "N", "FIGHT GUARD", "N", "WEAR GLOVES", "U", "OPEN CASE",
+        "GET RUBY",
+    ];
+
+    fn steps(session: &mut Session, commands: &[&str]) -> String {
+        commands.iter().map(|command| session.step(command)).collect()
+    }
+
+    /// The book's adventure with one fix switched back.
+    fn without(fix: Fix) -> &'static World {
+        Box::leak(Box::new(World { original: fix.bit(), ..STOCK }))
+    }
+
+    #[test]
+    fn the_walkthrough_wins_full_marks() {
+        let mut session = Session::new(&STOCK, false);
+        let out = steps(&mut session, &WALKTHROUGH);
+        assert!(session.won());
+        assert_eq!(session.score(), MAX_SCORE);
+        assert_eq!(session.moves(), WALKTHROUGH.len() as u32 - 1);
+        assert!(out.contains("CONGRATULATIONS! YOU'VE WON!"));
+    }
+
+    #[test]
+    fn only_commands_in_the_game_take_a_move() {
+        let mut session = Session::new(&STOCK, false);
+        steps(&mut session, &["SCORE", "XYZZY", "", "LOOK", "GET NOTHING", "N"]);
+        assert_eq!(session.moves(), 3);
+
+        // nothing is taken back from a counter that can't go any higher
+        let save = session.save().replace("moves 3", &format!("moves {}", u32::MAX));
+        assert!(session.restore(&save));
+        steps(&mut session, &["XYZZY", "TALK"]);
+        assert_eq!(session.moves(), u32::MAX);
+    }
+
+    #[test]
+    fn the_boat_drifts_back_unless_the_daemons_are_off() {
+        // across the river, then up the road and back
+        let crossed = WALKTHROUGH.iter().position(|&command| command == "LEAVE BOAT").unwrap() + 1;
+        for &(world, drifted) in &[(&STOCK, true), (without(Fix::Daemons), false)] {
+            let mut session = Session::new(world, false);
+            steps(&mut session, &WALKTHROUGH[..crossed]);
+            steps(&mut session, &["N", "LOOK", "LOOK", "S"]);
+            let at = if drifted { SOUTH_BANK } else { NORTH_BANK };
+            assert_eq!(session.state.positions[BOAT_OBJ], immobile!(at));
+        }
+    }
+
+    #[test]
+    fn the_guard_talks_and_patrols() {
+        let gate = WALKTHROUGH.iter().position(|&command| command == "FIGHT GUARD").unwrap();
+        let mut session = Session::new(&STOCK, false);
+        steps(&mut session, &WALKTHROUGH[..gate]);
+        assert!(session.step("TALK TO GUARD").contains("NOBODY GETS INTO"));
+        assert!(session.step("ASK GUARD ABOUT CASE").contains("IT'S ELECTRIFIED!"));
+        assert!(session.step("ASK GUARD ABOUT FAN").contains("THE GUARD JUST GRUNTS."));
+
+        // once past the gate, the guard paces the halls
+        steps(&mut session, &["FIGHT GUARD", "N"]);
+        let rooms: Vec<RoomId> = (0..6).map(|_| {
+            session.step("LOOK");
+            session.state.positions[GUARD] & 127
+        }).collect();
+        assert!(rooms.contains(&NARROW_HALL) && rooms.contains(&LARGE_HALL));
+    }
+
+    #[test]
+    fn drowning_ends_the_game_until_a_restart() {
+        let bank = WALKTHROUGH.iter().position(|&command| command == "BOAT").unwrap();
+        let mut session = Session::new(&STOCK, false);
+        steps(&mut session, &WALKTHROUGH[..bank]);
+        assert!(session.step("SWIM").contains("*** YOU HAVE DIED ***"));
+        assert!(session.dead());
+        assert!(session.prompt().contains("RESTART, RESTORE"));
+        session.step("RESTART");
+        assert!(!session.dead());
+        assert_eq!(session.here(), START_ROOM);
+        assert_eq!(session.moves(), 0);
+    }
+
+    #[test]
+    fn no_restore_after_the_end_where_saving_is_off() {
+        let bank = WALKTHROUGH.iter().position(|&command| command == "BOAT").unwrap();
+        let mut session = Session::new(&STOCK, false);
+        steps(&mut session, &WALKTHROUGH[..bank]);
+        session.step("SAVE");
+
+        let mut session = Session::new(without(Fix::Save), false);
+        steps(&mut session, &WALKTHROUGH[..bank]);
+        session.step("SWIM");
+        assert_eq!(session.prompt(), "\nWOULD YOU LIKE TO RESTART OR QUIT? ");
+        assert!(!session.completions().contains(&"RESTORE".to_string()));
+        session.step("RESTORE");
+        let _ = fs::remove_file(SAVE_FILE);
+        assert!(session.dead());
+        assert_eq!(session.prompt(), "\nWOULD YOU LIKE TO RESTART OR QUIT? ");
+    }
+
+    #[test]
+    fn a_saved_game_restores_as_it_was() {
+        let mut session = Session::new(&STOCK, false);
+        steps(&mut session, &WALKTHROUGH[..20]);
+        let save = session.save();
+        let mut restored = Session::new(&STOCK, false);
+        assert!(restored.restore(&save));
+        assert_eq!(restored.save(), save);
+        assert_eq!(restored.here(), session.here());
+        assert_eq!(restored.inventory(), session.inventory());
+    }
+
+    #[test]
+    fn damaged_saves_are_refused() {
+        let save = Session::new(&STOCK, false).save();
+        let damage = [
+            ("here", "0"), ("here", "20"), ("positions", "0 1 1"), ("positions", "0 1 1 130 0 3 133 0 4 5 135 0 140 8 144 146 0 200"),
+            ("shocks", "3"), ("milestones", "32"), ("timers", "5:2"), ("last_here", "99"), ("moves", "-1"), ("gloved", "maybe"),
+        ];
+        for (key, value) in damage.iter() {
+            let damaged: String = save.lines()
+                .map(|line| if line.split(' ').next() == Some(key) { format!("{} {}\n", key, value) } else { format!("{}\n", line) })
+                .collect();
+            assert_ne!(damaged, save);
+            assert!(!Session::new(&STOCK, false).restore(&damaged), "{} {}", key, value);
+        }
+    }
+
+    #[test]
+    fn the_move_counter_stops_at_its_limit() {
+        let save = Session::new(&STOCK, false).save().replace("moves 0", &format!("moves {}", u32::MAX));
+        let mut session = Session::new(&STOCK, false);
+        assert!(session.restore(&save));
+        steps(&mut session, &["LOOK", "N"]);
+        assert_eq!(session.moves(), u32::MAX);
+    }
+
+    #[test]
+    fn quitting_and_restarting_ask_first() {
+        let mut session = Session::new(&STOCK, true);
+        steps(&mut session, &["N", "QUIT"]);
+        assert_eq!(session.prompt(), "ARE YOU SURE? ");
+        session.step("NO");
+        assert!(!session.is_over());
+        steps(&mut session, &["RESTART", "YES"]);
+        assert_eq!(session.here(), START_ROOM);
+        steps(&mut session, &["QUIT", "YES"]);
+        assert!(session.is_over());
+
+        let mut unasked = Session::new(&STOCK, false);
+        unasked.step("QUIT");
+        assert!(unasked.is_over());
+    }
+
+    #[test]
+    fn each_fix_has_its_own_name_and_bit() {
+        for (i, &(fix, name, _)) in FIXES.iter().enumerate() {
+            for &(other, other_name, _) in &FIXES[i + 1..] {
+                assert_ne!(fix.bit(), other.bit());
+                assert_ne!(name, other_name);
+            }
+        }
+    }
+
+    #[test]
+    fn fixes_switch_back_to_the_book() {
+        let mut session = Session::new(&STOCK, false);
+        steps(&mut session, &["OPEN BOX", "GET BOTTLE"]);
+        assert!(session.step("OPEN BOX").contains("THE BOX IS ALREADY OPEN."));
+
+        let mut session = Session::new(without(Fix::Box), false);
+        steps(&mut session, &["OPEN BOX", "GET BOTTLE"]);
+        assert!(session.step("OPEN BOX").contains("SOMETHING FELL OUT!"));
+    }
+
+    #[test]
+    fn words_are_three_letters_whatever_is_typed() {
+        assert_eq!(words("GET  BOTTLE"), ["GET", "BOT"]);
+        assert_eq!(words("SHAKE SHAKER"), ["SHA", "SAL"]);
+        assert_eq!(words("ÉTÉ ☃☃☃☃ A"), ["ÉTÉ", "☃☃☃", "A"]);
+        let mut session = Session::new(&STOCK, false);
+        assert!(session.step("ÜBERGEBEN ☃☃☃☃").contains("I DON'T KNOW HOW TO DO THAT."));
+    }
+
+    #[test]
+    fn actions_are_the_commands_that_change_something() {
+        let mut session = Session::new(&STOCK, false);
+        let actions = session.actions();
+        assert!(actions.contains(&"OPEN BOX".to_string()));
+        assert!(actions.contains(&"NORTH".to_string()));
+        assert!(!actions.contains(&"LOOK".to_string()));
+        assert!(!actions.contains(&"READ DIARY".to_string()));
+        assert_eq!(session.moves(), 0);
+
+        let hint = session.step("WHAT CAN I DO");
+        assert!(hint.contains("YOU COULD TRY:\n"));
+        assert!(hint.contains("    OPEN BOX\n"));
+        assert_eq!(session.moves(), 0);
+        let mut session = Session::new(without(Fix::Hint), false);
+        assert!(session.step("HINT").contains("I DON'T KNOW HOW TO DO THAT."));
+    }
+
+    #[test]
+    fn a_session_can_be_looked_into_without_playing_it() {
+        let mut session = Session::new(&STOCK, true);
+        assert!(session.take_output().contains("IN YOUR LIVING ROOM."));
+        assert_eq!(session.world().room(session.here()), "IN YOUR LIVING ROOM.");
+        let here: Vec<_> = session.objects_here().into_iter().map(|id| session.world().object(id)).collect();
+        assert_eq!(here, ["AN OLD DIARY", "A SMALL BOX"]);
+        assert!(session.inventory().is_empty());
+        assert_eq!(session.prompt(), "\nWHAT NOW? ");
+
+        session.step("GET DIARY");
+        let held: Vec<_> = session.inventory().into_iter().map(|id| session.world().object(id)).collect();
+        assert_eq!(held, ["AN OLD DIARY"]);
+        assert_eq!((session.score(), session.moves()), (0, 1));
+
+        session.step("Q");
+        assert_eq!(session.prompt(), "ARE YOU SURE? ");
+        assert!(!session.is_over());
+        session.step("YES");
+        assert!(session.is_over());
+        assert_eq!(session.step("LOOK"), "");
+    }
+
+    #[test]
+    fn an_imported_world_plays_in_a_session() {
+        let world = crate::import("26000 DATA A RUBY,RUB,2\n27000 DATA IN A CAVE.,0,0,0,0,0,0\n27010 DATA IN A GROTTO.,0,0,0,0,0,0\n").unwrap();
+        assert_eq!(world.room(2), "IN A GROTTO.");
+        assert_eq!(world.object(1), "A RUBY");
+        let mut session = Session::new(world, false);
+        assert!(session.take_output().contains("IN A CAVE."));
+        assert!(session.objects_here().is_empty());
+        assert_eq!(session.step("GET RUBY"), "THAT ISN'T HERE!\n");
+    }
+}
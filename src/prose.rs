@@ -0,0 +1,373 @@
+//! The game in sentence case and modern punctuation, for players who find
+//! a screen of capitals hard going.
+//!
+//! Each message the game prints has its modern wording in the tables below,
+//! looked up by the book's text, line by line, as it's printed. Messages
+//! with a name or number in them are templates, with `{}` where it goes,
+//! itself looked up in turn. Anything not in the tables, like the text of
+//! an imported world, is printed as it is.
+
+use super::wrap::BREAK;
+
+/// Where a template takes whatever is in that place in the message.
+const HOLE: &str = "{}";
+
+/// Like HOLE, but for a list of directions, to be given as "a, b or c".
+const LIST: &str = "{or}";
+
+/// The book's messages, and the rooms, objects and other words that go into them.
+static MESSAGES: &[(&str, &str)] = &[
+    // the introduction
+    ("ALL YOUR LIFE YOU HAD HEARD THE STORIES|\
+      ABOUT YOUR CRAZY UNCLE SIMON. HE WAS AN|\
+      INVENTOR, WHO KEPT DISAPPEARING FOR|\
+      LONG PERIODS OF TIME, NEVER TELLING|\
+      ANYONE WHERE HE HAD BEEN.",
+     "All your life you had heard the stories about your crazy Uncle Simon. He was an \
+      inventor who kept disappearing for long periods of time, never telling anyone \
+      where he had been."),
+    ("YOU NEVER BELIEVED THE STORIES, BUT|\
+      WHEN YOUR UNCLE DIED AND LEFT YOU HIS|\
+      DIARY, YOU LEARNED THAT THEY WERE TRUE.|\
+      YOUR UNCLE HAD DISCOVERED A MAGIC|\
+      LAND, AND A SECRET FORMULA THAT COULD|\
+      TAKE HIM THERE. IN THAT LAND WAS A|\
+      MAGIC RUBY, AND HIS DIARY CONTAINED|\
+      THE INSTRUCTIONS FOR GOING THERE TO|\
+      FIND IT.",
+     "You never believed the stories, but when your uncle died and left you his diary, \
+      you learned that they were true. Your uncle had discovered a magic land, and a \
+      secret formula that could take him there. In that land was a magic ruby, and his \
+      diary contained the instructions for going there to find it."),
+
+    // prompts
+    ("\nWHAT NOW? ", "\nWhat now? "),
+    ("ARE YOU SURE? ", "Are you sure? "),
+    ("\nWOULD YOU LIKE TO PLAY AGAIN? ", "\nWould you like to play again? "),
+    ("\nWOULD YOU LIKE TO RESTART, RESTORE\nOR QUIT? ", "\nWould you like to restart, restore or quit? "),
+    ("\nWOULD YOU LIKE TO RESTART OR QUIT? ", "\nWould you like to restart or quit? "),
+
+    // looking around
+    ("YOU CAN GO:", "There's no way out."),
+    ("YOU CAN SEE:", "You can see:"),
+    ("THERE IS NOTHING OF INTEREST HERE.", "Nothing of interest."),
+    ("NOTHING OF INTEREST", "nothing of interest"),
+
+    // rooms
+    ("IN YOUR LIVING ROOM.", "in your living room."),
+    ("IN THE KITCHEN.", "in the kitchen."),
+    ("IN THE LIBRARY.", "in the library."),
+    ("IN THE FRONT YARD.", "in the front yard."),
+    ("IN THE GARAGE.", "in the garage."),
+    ("IN AN OPEN FIELD.", "in an open field."),
+    ("AT THE EDGE OF A FOREST.", "at the edge of a forest."),
+    ("ON A BRANCH OF A TREE.", "on a branch of a tree."),
+    ("ON A LONG, WINDING ROAD.", "on a long, winding road."),
+    ("ON THE SOUTH BANK OF A RIVER.", "on the south bank of a river."),
+    ("INSIDE THE WOODEN BOAT.", "inside the wooden boat."),
+    ("ON THE NORTH BANK OF A RIVER.", "on the north bank of a river."),
+    ("ON A WELL-TRAVELED ROAD.", "on a well-traveled road."),
+    ("IN FRONT OF A LARGE CASTLE.", "in front of a large castle."),
+    ("IN A NARROW HALL.", "in a narrow hall."),
+    ("IN A LARGE HALL.", "in a large hall."),
+    ("ON THE TOP OF A TREE.", "at the top of a tree."),
+
+    // objects
+    ("AN OLD DIARY", "an old diary"),
+    ("A SMALL BOX", "a small box"),
+    ("A CABINET", "a cabinet"),
+    ("A SALT SHAKER", "a salt shaker"),
+    ("A DICTIONARY", "a dictionary"),
+    ("A WOODEN BARREL", "a wooden barrel"),
+    ("A SMALL BOTTLE", "a small bottle"),
+    ("A LADDER", "a ladder"),
+    ("A SHOVEL", "a shovel"),
+    ("A TREE", "a tree"),
+    ("A GOLDEN SWORD", "a golden sword"),
+    ("A WOODEN BOAT", "a wooden boat"),
+    ("A MAGIC FAN", "a magic fan"),
+    ("A NASTY-LOOKING GUARD", "a nasty-looking guard"),
+    ("A GLASS CASE", "a glass case"),
+    ("A GLOWING RUBY", "a glowing ruby"),
+    ("A PAIR OF RUBBER GLOVES", "a pair of rubber gloves"),
+
+    // getting about
+    ("GO WHERE?", "Go where?"),
+    ("PLEASE GIVE A DIRECTION!", "Which way?"),
+    ("YOU CAN'T GO THERE!", "You can't go that way."),
+    ("THE GUARD WON'T LET YOU!", "The guard won't let you past."),
+
+    // carrying things
+    ("YOU ARE WEARING RUBBER GLOVES.", "You're wearing rubber gloves."),
+    ("YOU ARE CARRYING:", "You're carrying:"),
+    ("NOTHING", "nothing"),
+    ("TAKEN.", "Taken."),
+    ("DROPPED.", "Dropped."),
+    ("YOU ALREADY HAVE IT!", "You already have it."),
+    ("YOU CAN'T GET THAT!", "You can't take that."),
+    ("YOU CAN'T CARRY ANY MORE.", "You can't carry any more."),
+    ("YOU DON'T HAVE THAT!", "You don't have that."),
+    ("THAT ISN'T HERE!", "That isn't here."),
+
+    // looking at things
+    ("IT LOOKS LIKE GROUND!", "It looks like ground."),
+    ("IT LOOKS LIKE SOMETHING'S BURIED HERE.", "It looks like something's buried here."),
+    ("THERE'S A HOLE HERE.", "There's a hole here."),
+    ("THERE'S SOMETHING WRITTEN ON IT!", "There's something written on it."),
+    ("THERE'S A JEWEL INSIDE!", "There's a jewel inside."),
+    ("IT'S FILLED WITH RAINWATER.", "It's filled with rainwater."),
+    ("YOU SEE NOTHING UNUSUAL.", "You see nothing unusual."),
+
+    // opening, reading and pouring
+    ("SOMETHING FELL OUT!", "Something fell out!"),
+    ("THE BOX IS ALREADY OPEN.", "The box is already open."),
+    ("THERE'S SOMETHING INSIDE!", "There's something inside!"),
+    ("THE CABINET IS ALREADY OPEN.", "The cabinet is already open."),
+    ("THE CASE IS ALREADY OPEN.", "The case is already open."),
+    ("THE GLOVES INSULATE AGAINST THE|ELECTRICITY! THE CASE OPENS!",
+     "The gloves insulate you from the electricity, and the case opens."),
+    ("THE CASE IS ELECTRIFIED!", "The case is electrified!"),
+    ("ONE MORE JOLT LIKE THAT COULD KILL YOU.", "One more jolt like that could kill you."),
+    ("YOU CAN'T OPEN THAT!", "You can't open that."),
+    ("IT SAYS: 'ADD SODIUM CHLORIDE PLUS THE|FORMULA TO RAINWATER, TO REACH THE|OTHER WORLD.'",
+     "It says, \"Add sodium chloride plus the formula to rainwater to reach the other world.\""),
+    ("IT SAYS: SODIUM CHLORIDE IS|COMMON TABLE SALT.", "It says that sodium chloride is common table salt."),
+    ("IT READS: 'SECRET FORMULA'.", "It reads \"Secret Formula\"."),
+    ("YOU CAN'T READ THAT!", "You can't read that."),
+    ("THE SALT SHAKER IS EMPTY.", "The salt shaker is empty."),
+    ("THE BOTTLE IS EMPTY.", "The bottle is empty."),
+    ("YOU CAN'T POUR THAT!", "You can't pour that."),
+    ("POURED!", "Poured."),
+    ("THERE IS AN EXPLOSION!", "There is an explosion!"),
+    ("EVERYTHING GOES BLACK!", "Everything goes black..."),
+    ("SUDDENLY YOU ARE. . .", "and suddenly you are..."),
+    (". . .SOMEWHERE ELSE!", "...somewhere else!"),
+
+    // climbing, digging, rowing and the rest
+    ("YOU CAN'T REACH THE BRANCHES!", "You can't reach the branches."),
+    ("THE LADDER SINKS UNDER YOUR WEIGHT!|IT DISAPPEARS INTO THE GROUND!",
+     "The ladder sinks under your weight and disappears into the ground!"),
+    ("WHATEVER FOR?", "Whatever for?"),
+    ("IT WON'T DO ANY GOOD.", "It won't do any good."),
+    ("YOU GRAB THE LOWEST BRANCH OF THE|TREE AND PULL YOURSELF UP. . . .",
+     "You grab the lowest branch of the tree and pull yourself up..."),
+    ("YOU GRAB A HIGHER BRANCH OF THE|TREE AND PULL YOURSELF UP. . . .",
+     "You grab a higher branch of the tree and pull yourself up..."),
+    ("WHEE! THAT WAS FUN!", "Whee! That was fun!"),
+    ("YOU CAN'T DIG THAT!", "You can't dig that."),
+    ("YOU DON'T HAVE A SHOVEL!", "You don't have a shovel."),
+    ("YOU DON'T FIND ANYTHING.", "You don't find anything."),
+    ("THERE'S NOTHING ELSE THERE!", "There's nothing else there."),
+    ("THERE'S SOMETHING THERE!", "There's something there!"),
+    ("YOU'RE NOT IN A BOAT!", "You're not in a boat."),
+    ("YOU DON'T HAVE AN OAR!", "You don't have an oar."),
+    ("HOW CAN YOU ROW THAT?", "How can you row that?"),
+    ("YOU PLUNGE INTO THE RIVER...", "You plunge into the river..."),
+    ("THERE'S NOWHERE TO SWIM HERE.", "There's nowhere to swim here."),
+    ("YOU CAN'T WAVE THAT!", "You can't wave that."),
+    ("YOU DON'T HAVE A FAN!", "You don't have a fan."),
+    ("YOU FEEL A REFRESHING BREEZE!", "You feel a refreshing breeze."),
+    ("A POWERFUL BREEZE PROPELS THE BOAT|TO THE OPPOSITE SHORE!",
+     "A powerful breeze propels the boat to the opposite shore!"),
+    ("HUH?", "Huh?"),
+    ("THERE'S NO GUARD HERE!", "There's no guard here."),
+    ("YOU DON'T HAVE A WEAPON!", "You don't have a weapon."),
+    ("THE GUARD, NOTICING YOUR SWORD,|WISELY RETREATS INTO THE CASTLE.",
+     "The guard, noticing your sword, wisely retreats into the castle."),
+    ("WHOM DO YOU WANT TO FIGHT?", "Whom do you want to fight?"),
+    ("YOU CAN'T FIGHT THEM!", "You can't fight that."),
+    ("YOU ARE ALREADY WEARING THE RUBBER GLOVES.", "You're already wearing the rubber gloves."),
+    ("YOU DON'T HAVE THE GLOVES.", "You don't have the gloves."),
+    ("YOU ARE NOW WEARING THE GLOVES.", "You're now wearing the gloves."),
+    ("YOU CAN'T WEAR THAT!", "You can't wear that."),
+    ("I DON'T KNOW HOW TO DO THAT.", "I don't know how to do that."),
+
+    // asking what to do it to
+    ("WHAT DO YOU WANT TO EXAMINE?", "What do you want to examine?"),
+    ("WHAT DO YOU WANT TO GET?", "What do you want to take?"),
+    ("WHAT DO YOU WANT TO DROP?", "What do you want to drop?"),
+    ("WHAT DO YOU WANT TO OPEN?", "What do you want to open?"),
+    ("WHAT DO YOU WANT TO READ?", "What do you want to read?"),
+    ("WHAT DO YOU WANT TO POUR?", "What do you want to pour?"),
+    ("WHAT DO YOU WANT TO CLIMB?", "What do you want to climb?"),
+    ("WHAT DO YOU WANT TO WAVE?", "What do you want to wave?"),
+    ("WHAT DO YOU WANT TO WEAR?", "What do you want to wear?"),
+
+    // the boat and the guard, by themselves
+    ("THE CURRENT CARRIES THE BOAT BACK|TO THE SOUTH BANK.", "The current carries the boat back to the south bank."),
+    ("THE GUARD RETURNS TO HIS POST.", "The guard returns to his post."),
+    ("THE GUARD MARCHES IN.", "The guard marches in."),
+    ("THE GUARD MARCHES OFF.", "The guard marches off."),
+    ("THE GUARD EYES YOUR SWORD NERVOUSLY.", "The guard eyes your sword nervously."),
+    ("THE GUARD GROWLS: 'NOBODY GETS INTO|THE CASTLE ON MY WATCH!'",
+     "\"Nobody gets into the castle on my watch!\" the guard growls."),
+    ("'IT'S ELECTRIFIED! TOUCH IT AND|YOU'LL GET THE SHOCK OF YOUR LIFE!'",
+     "\"It's electrified! Touch it and you'll get the shock of your life!\""),
+    ("'THE RUBY? IT'S SAFE IN ITS CASE,|AND THAT'S WHERE IT STAYS.'",
+     "\"The ruby? It's safe in its case, and that's where it stays.\""),
+    ("'PUT THAT THING AWAY!'", "\"Put that thing away!\""),
+    ("THE GUARD JUST GRUNTS.", "The guard just grunts."),
+    ("THAT'S NOT VERY TALKATIVE.", "That's not very talkative."),
+    ("WHOM DO YOU WANT TO TALK TO?", "Whom do you want to talk to?"),
+    ("WHOM DO YOU WANT TO ASK?", "Whom do you want to ask?"),
+    ("WHAT DO YOU WANT TO ASK ABOUT?", "What do you want to ask about?"),
+
+    // about the game
+    ("YOU COULD TRY:", "You could try:"),
+    ("NOTHING, BY THE LOOKS OF IT.", "nothing, by the looks of it."),
+    ("SAVED.", "Saved."),
+    ("SORRY, THE GAME COULDN'T BE SAVED.", "Sorry, the game couldn't be saved."),
+    ("THERE'S NO SAVED GAME TO RESTORE.", "There's no saved game to restore."),
+    ("THE SAVED GAME IS DAMAGED.", "The saved game is damaged."),
+
+    // the end
+    ("THE SHOCK STOPS YOUR HEART!", "The shock stops your heart."),
+    ("THE CURRENT DRAGS YOU UNDER!", "The current drags you under."),
+    ("*** YOU HAVE DIED ***", "*** You have died ***"),
+    ("CONGRATULATIONS! YOU'VE WON!", "Congratulations! You've won!"),
+    ("MASTER ADVENTURER", "Master Adventurer"),
+    ("SEASONED EXPLORER", "Seasoned Explorer"),
+    ("ADVENTURER", "Adventurer"),
+    ("NOVICE", "Novice"),
+    ("BEGINNER", "Beginner"),
+];
+
+/// The ways out of a room, for the list in YOU CAN GO. On their own,
+/// as in the commands WHAT CAN I DO suggests, they're left as typed.
+static DIRECTIONS: &[(&str, &str)] = &[
+    ("NORTH", "north"), ("SOUTH", "south"), ("EAST", "east"),
+    ("WEST", "west"), ("DOWN", "down"), ("UP", "up"),
+];
+
+/// Messages with something in them that changes.
+static TEMPLATES: &[(&str, &str)] = &[
+    ("YOU ARE {}", "You are {}"),
+    ("YOU CAN GO:{or}", "You can go {or}."),
+    ("    {}", "    {}"),
+    ("YOU HAVE SCORED {} OUT OF {} POINTS|IN {} MOVES.", "You have scored {} out of {} points in {} moves."),
+    ("THAT EARNS YOU THE RANK OF|{}.", "That earns you the rank of {}."),
+];
+
+/// The game's text in modern prose, as far as the tables go.
+pub fn translate(text: &str) -> String {
+    let lines: Vec<String> = text.split('\n')
+        .map(|line| modern(line).unwrap_or_else(|| line.to_string()))
+        .collect();
+    lines.join("\n")
+}
+
+/// One message in modern prose, if it's in the tables.
+pub fn modern(text: &str) -> Option<String> {
+    match exact(text) {
+        Some(modern) => Some(modern.to_string()),
+        None => TEMPLATES.iter().find_map(|&(book, modern)| fill(book, modern, text))
+    }
+}
+
+/// One of the messages that never change, like a prompt, in modern prose.
+pub fn exact(text: &str) -> Option<&'static str> {
+    MESSAGES.iter().find(|&&(book, _)| book == text).map(|&(_, modern)| modern)
+}
+
+/// The modern template filled in from the text, if it's the book's template filled in.
+fn fill(book: &str, modern: &str, text: &str) -> Option<String> {
+    let book = book.replace(LIST, HOLE);
+    let mut parts = book.split(HOLE);
+    let mut rest = text.strip_prefix(parts.next()?)?;
+    let mut taken = Vec::new();
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        let end = match parts.peek() {
+            Some(_) => rest.find(part)?,
+            None => rest.strip_suffix(part)?.len()
+        };
+        taken.push(&rest[..end]);
+        rest = &rest[end + part.len()..];
+    }
+
+    let mut taken = taken.into_iter();
+    let mut filled = String::new();
+    let mut rest = modern;
+    while let Some(start) = rest.find('{') {
+        let len = rest[start..].find('}')? + 1;
+        let hole = &rest[start..start + len];
+        let words = taken.next()?;
+        filled += &rest[..start];
+        filled += &if hole == LIST { list(words)? } else { word(words)? };
+        rest = &rest[start + len..];
+    }
+    filled += rest;
+    Some(filled.replace(BREAK, " "))
+}
+
+/// What goes in a hole, in modern prose. Numbers go as they are, but
+/// words have to be in the tables, or the whole message stays as it was.
+fn word(text: &str) -> Option<String> {
+    if text.bytes().any(|byte| byte.is_ascii_alphabetic()) {
+        modern(text)
+    } else {
+        Some(text.to_string())
+    }
+}
+
+/// NORTH SOUTH EAST as "north, south or east".
+fn list(text: &str) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace()
+        .map(|word| DIRECTIONS.iter().find(|&&(book, _)| book == word).map(|&(_, modern)| modern))
+        .collect::<Option<_>>()?;
+    Some(match words.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Session, STOCK};
+
+    #[test]
+    fn templates_are_filled_in_from_the_tables() {
+        let filled = fill("YOU ARE {}", "You are {}", "YOU ARE IN YOUR LIVING ROOM.");
+        assert_eq!(filled.as_deref(), Some("You are in your living room."));
+        assert_eq!(fill("YOU ARE {}", "You are {}", "YOU ARE IN A CAVE."), None);
+        assert_eq!(fill("YOU ARE {}", "You are {}", "THEY ARE HERE"), None);
+        assert_eq!(
+            modern("YOU HAVE SCORED 25 OUT OF 100 POINTS|IN 12 MOVES.").as_deref(),
+            Some("You have scored 25 out of 100 points in 12 moves.")
+        );
+        assert_eq!(modern("    AN OLD DIARY").as_deref(), Some("    an old diary"));
+    }
+
+    #[test]
+    fn directions_are_listed() {
+        assert_eq!(list(" NORTH").as_deref(), Some("north"));
+        assert_eq!(list(" NORTH SOUTH EAST").as_deref(), Some("north, south or east"));
+        assert_eq!(list(" NORTH SIDEWAYS"), None);
+        assert_eq!(modern("YOU CAN GO: UP DOWN").as_deref(), Some("You can go up or down."));
+    }
+
+    #[test]
+    fn what_is_not_in_the_tables_is_left_alone() {
+        assert_eq!(exact("TAKEN."), Some("Taken."));
+        assert_eq!(exact("TAKEN"), None);
+        assert_eq!(translate("TAKEN.\nXYZZY\n"), "Taken.\nXYZZY\n");
+    }
+
+    #[test]
+    fn the_whole_game_is_in_modern_prose() {
+        let mut session = Session::new(&STOCK, false);
+        session.set_modern(true);
+        let mut out = session.take_output();
+        for command in ["OPEN BOX", "GET BOTTLE", "E", "OPEN CABINET", "GET SALT", "W", "N", "W", "POUR SALT",
+                        "SCORE", "POUR BOTTLE", "I", "EXAMINE BOTTLE", "XYZZY", "GET", "N", "Q"] {
+            out += &session.step(command);
+        }
+        let shouted: Vec<&str> = out.lines()
+            .filter(|line| line.chars().any(|c| c.is_ascii_alphabetic()))
+            .filter(|line| !line.chars().any(|c| c.is_ascii_lowercase()))
+            .collect();
+        assert_eq!(shouted, Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,178 @@
+//! Serves games over telnet, a separate one to each connection, or one
+//! game for everyone sharing the world.
+//!
+//! Served games can't SAVE or RESTORE, since everyone would share one
+//! LAMPTON.SAV in the server's directory.
+
+use std::io::{self, prelude::*, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::shared::{PlayerId, SharedWorld};
+use super::wrap::BOOK_WIDTH;
+use super::{play, Fix, Terminal, World};
+
+/// Games going at once. Anyone else is turned away.
+const MAX_SESSIONS: usize = 8;
+
+/// A player who types nothing for this long is disconnected.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+const IAC: u8 = 255;
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream
+}
+
+impl Terminal for Connection {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.writer.write_all(&telnet(text))
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = Vec::new();
+        match self.reader.read_until(b'\n', &mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(typed(&line))),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock || err.kind() == io::ErrorKind::TimedOut => {
+                self.print("\nYOU'VE BEEN IDLE TOO LONG. GOODBYE.\n")?;
+                Ok(None)
+            }
+            Err(err) => Err(err)
+        }
+    }
+}
+
+fn telnet(text: &str) -> Vec<u8> {
+    text.replace('\n', "\r\n").into_bytes()
+}
+
+/// What the player typed, without the client's telnet commands or line ending.
+fn typed(line: &[u8]) -> String {
+    let mut typed = String::new();
+    let mut bytes = line.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            IAC => { bytes.nth(1); } // and the command and option after it
+            b' '..=b'~' => typed.push(byte as char),
+            _ => {}
+        }
+    }
+    typed
+}
+
+/// Gives the seat back when the game ends, however it ends.
+struct Seat(Arc<AtomicUsize>);
+
+impl Drop for Seat {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Listens on `addr` until killed. If `shared`, everyone plays in the same world.
+pub fn serve(addr: &str, world: &'static World, confirm: bool, shared: bool) -> io::Result<()> {
+    let world: &World = Box::leak(Box::new(World { original: world.original | Fix::Save.bit(), ..*world }));
+    let shared = if shared { Some(Arc::new(SharedWorld::new(world, confirm))) } else { None };
+    let listener = TcpListener::bind(addr)?;
+    eprintln!("lampton: serving on {}", listener.local_addr()?);
+    let sessions = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue
+        };
+        let seat = Seat(Arc::clone(&sessions));
+        if sessions.fetch_add(1, Ordering::SeqCst) >= MAX_SESSIONS {
+            let _ = stream.write_all(b"SORRY, EVERY GAME IS TAKEN. TRY AGAIN LATER.\r\n");
+            continue; // the seat goes back
+        }
+        let shared = shared.clone();
+        thread::spawn(move || {
+            let _seat = seat;
+            let _ = match shared {
+                Some(shared) => join(stream, &shared),
+                None => connect(stream, world, confirm)
+            };
+        });
+    }
+    Ok(())
+}
+
+fn connect(stream: TcpStream, world: &'static World, confirm: bool) -> io::Result<()> {
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    let mut connection = Connection { reader: BufReader::new(stream.try_clone()?), writer: stream };
+    play(&mut connection, world, confirm, BOOK_WIDTH, false)
+}
+
+fn join(stream: TcpStream, shared: &SharedWorld) -> io::Result<()> {
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut connection = Connection { reader: BufReader::new(stream.try_clone()?), writer: stream };
+    let (id, inbox) = loop {
+        connection.print("WHAT IS YOUR NAME? ")?;
+        let name = match connection.read_line()? {
+            Some(name) => name,
+            None => return Ok(())
+        };
+        if name.trim().is_empty() {
+            continue;
+        }
+        match shared.join(&name) {
+            Some(joined) => break joined,
+            None => connection.print("SOMEONE ELSE HERE GOES BY THAT NAME.\n")?
+        }
+    };
+    // other players' doings can come at any time
+    thread::spawn(move || {
+        for text in inbox {
+            if writer.write_all(&telnet(&text)).is_err() {
+                break;
+            }
+        }
+    });
+    let played = take_turns(&mut connection, shared, id);
+    shared.leave(id);
+    played
+}
+
+fn take_turns(connection: &mut Connection, shared: &SharedWorld, id: PlayerId) -> io::Result<()> {
+    while let Some(command) = connection.read_line()? {
+        if !shared.command(id, &command) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::STOCK;
+
+    #[test]
+    fn telnet_commands_and_line_endings_are_not_typed() {
+        assert_eq!(typed(b"GET BOX\r\n"), "GET BOX");
+        assert_eq!(typed(&[IAC, 251, 1, b'N', 0xc3, 0xa9, b'\r', b'\n']), "N");
+        assert_eq!(telnet("A\nB\n"), b"A\r\nB\r\n");
+    }
+
+    #[test]
+    fn a_game_is_played_over_a_connection() -> io::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let mut client = TcpStream::connect(listener.local_addr()?)?;
+        let (stream, _) = listener.accept()?;
+        let game = thread::spawn(move || connect(stream, &STOCK, false));
+        client.write_all(b"OPEN BOX\r\nQ\r\n")?;
+        let mut out = String::new();
+        client.read_to_string(&mut out)?;
+        game.join().unwrap()?;
+        assert!(out.contains("SOMETHING FELL OUT!\r\n"));
+        assert!(!out.replace("\r\n", "").contains('\n'));
+        Ok(())
+    }
+}
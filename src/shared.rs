@@ -0,0 +1,323 @@
+//! Several players in one world, each with their own place and belongings.
+//! Everything else (what lies about, the puzzles, the score and the turn
+//! clock) they share, and they see each other come and go.
+//!
+//! Turns are taken one at a time, so when two players grab for the same
+//! thing, the first gets it and the second is told who has it. There's
+//! only the one game, so nobody can SAVE, RESTORE or RESTART it.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, MutexGuard};
+
+use super::wrap::BOOK_WIDTH;
+use super::{parser, words, Fix, ObjId, Outcome, Question, RoomId, State, World, GLOVES, INVENTORY, START_ROOM};
+
+/// Where the things in other players' hands are kept during a turn:
+/// nowhere, but not 0, which the puzzles take to mean not found yet.
+const CARRIED: RoomId = immobile!(0);
+
+pub type PlayerId = usize;
+
+struct Player {
+    id: PlayerId,
+    name: String,
+    here: RoomId,
+    last_here: RoomId,
+    gloved: bool,
+    carrying: Vec<ObjId>,
+    /// A command, or whether they're sure about quitting.
+    question: Question,
+    /// Everything the player is to see.
+    inbox: Sender<String>
+}
+
+pub struct SharedWorld {
+    game: Mutex<Game>
+}
+
+struct Game {
+    /// The world as the player taking a turn sees it.
+    state: State,
+    /// Whether quitting asks ARE YOU SURE? first.
+    confirm: bool,
+    players: Vec<Player>,
+    next_id: PlayerId
+}
+
+impl SharedWorld {
+    /// Unless `confirm`, players quit without being asked ARE YOU SURE?
+    pub fn new(world: &'static World, confirm: bool) -> Self {
+        let original = world.original | Fix::Save.bit() | Fix::Restart.bit();
+        let world = Box::leak(Box::new(World { original, ..*world }));
+        let game = Game { state: State::new_game(world), confirm, players: Vec::new(), next_id: 0 };
+        SharedWorld { game: Mutex::new(game) }
+    }
+
+    /// Adds a player at the start, and shows them the introduction.
+    /// Everything they're to see comes through the receiver.
+    /// None if someone already goes by that name.
+    pub fn join(&self, name: &str) -> Option<(PlayerId, Receiver<String>)> {
+        let mut game = self.lock();
+        let name = name.trim().to_ascii_uppercase();
+        if game.players.iter().any(|player| player.name == name) {
+            return None;
+        }
+        game.tell(&format!("{} HAS JOINED THE GAME.", name), |_| true);
+        let (inbox, receiver) = mpsc::channel();
+        let id = game.next_id;
+        game.next_id += 1;
+        game.players.push(Player {
+            id, name, here: START_ROOM, last_here: START_ROOM, gloved: false, carrying: Vec::new(),
+            question: Question::Command, inbox
+        });
+        let me = game.players.len() - 1;
+        game.swap_in(me);
+        game.state.intro();
+        game.state.look_around();
+        game.swap_out(me);
+        game.show(me, true);
+        Some((id, receiver))
+    }
+
+    /// Plays a player's command. False once it's taken them out of the game.
+    pub fn command(&self, id: PlayerId, input: &str) -> bool {
+        let mut game = self.lock();
+        match game.players.iter().position(|player| player.id == id) {
+            Some(me) => game.command(me, input),
+            None => false
+        }
+    }
+
+    /// Takes a player out of the game, leaving what they had where they were.
+    pub fn leave(&self, id: PlayerId) {
+        let mut game = self.lock();
+        if let Some(me) = game.players.iter().position(|player| player.id == id) {
+            let msg = format!("{} HAS LEFT THE GAME.", game.players[me].name);
+            game.remove(me, &msg);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Game> {
+        // a turn that panicked can't have left things any worse than half done
+        self.game.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Game {
+    fn command(&mut self, me: usize, input: &str) -> bool {
+        let input = input.trim().to_ascii_uppercase();
+        if self.players[me].question == Question::SureQuit {
+            if input.starts_with('Y') {
+                let msg = format!("{} HAS LEFT THE GAME.", self.players[me].name);
+                self.remove(me, &msg);
+                return false;
+            }
+            self.players[me].question = Question::Command;
+            self.show(me, true);
+            return true;
+        }
+        let was_here = self.players[me].here;
+        let had = self.players[me].carrying.clone();
+        self.swap_in(me);
+        let mut playing = true;
+        if let Some(holder) = self.holder(me, &input) {
+            outln!(self.state, "{} HAS IT!", holder);
+        } else if !input.is_empty() {
+            match parser(&input, &mut self.state) {
+                Outcome::Continue => self.state.tick(),
+                Outcome::Untimed => {}
+                Outcome::Quit if self.confirm => self.players[me].question = Question::SureQuit,
+                Outcome::Quit | Outcome::Restart => playing = false
+            }
+        }
+        let ended = if self.state.won {
+            Some("HAS WON!")
+        } else if self.state.dead {
+            Some("HAS DIED.")
+        } else {
+            None
+        };
+        if ended.is_some() {
+            self.state.game_over();
+            self.state.won = false;
+            self.state.dead = false;
+        }
+        self.swap_out(me);
+        self.show(me, playing && ended.is_none());
+
+        let player = &self.players[me];
+        let (id, name, here) = (player.id, player.name.clone(), player.here);
+        let others_in = |room: RoomId| move |other: &Player| other.id != id && other.here == room;
+        for &obj in player.carrying.iter().filter(|obj| !had.contains(obj)) {
+            self.tell(&format!("{} TAKES {}.", name, the(self.state.world.objects[obj].name)), others_in(was_here));
+        }
+        for &obj in had.iter().filter(|&&obj| self.state.positions[obj] == was_here) {
+            self.tell(&format!("{} DROPS {}.", name, the(self.state.world.objects[obj].name)), others_in(was_here));
+        }
+        if here != was_here {
+            self.tell(&format!("{} LEAVES.", name), others_in(was_here));
+            self.tell(&format!("{} ARRIVES.", name), others_in(here));
+        }
+        match ended {
+            Some(how) => self.remove(me, &format!("{} {}", name, how)),
+            None if !playing => self.remove(me, &format!("{} HAS LEFT THE GAME.", name)),
+            None => {}
+        }
+        playing && ended.is_none()
+    }
+
+    /// Who else here has what the player's trying to GET, if anyone.
+    fn holder(&self, me: usize, input: &str) -> Option<String> {
+        let tag = match words(input).as_slice() {
+            ["GET", tag] | ["TAK", tag] => *tag,
+            _ => return None
+        };
+        let (obj, _) = self.state.lookup_object(tag)?;
+        let player = &self.players[me];
+        self.players.iter()
+            .find(|other| other.id != player.id && other.here == player.here && other.carrying.contains(&obj))
+            .map(|other| other.name.clone())
+    }
+
+    fn swap_in(&mut self, me: usize) {
+        let player = &self.players[me];
+        let state = &mut self.state;
+        state.here = player.here;
+        state.last_here = player.last_here;
+        state.gloved = player.gloved;
+        for &obj in &player.carrying {
+            state.positions[obj] = INVENTORY;
+        }
+        state.company = self.players.iter()
+            .filter(|other| other.id != player.id)
+            .map(|other| (other.name.clone(), other.here))
+            .collect();
+    }
+
+    fn swap_out(&mut self, me: usize) {
+        let player = &mut self.players[me];
+        let state = &mut self.state;
+        player.here = state.here;
+        player.last_here = state.last_here;
+        player.gloved = state.gloved;
+        player.carrying = (1..state.positions.len()).filter(|&obj| state.positions[obj] == INVENTORY).collect();
+        for &obj in &player.carrying {
+            state.positions[obj] = CARRIED;
+        }
+    }
+
+    /// Sends a player what the game printed for them.
+    fn show(&mut self, me: usize, prompt: bool) {
+        let mut text = self.state.take_output(BOOK_WIDTH, false);
+        if prompt {
+            text += self.prompt(&self.players[me]);
+        }
+        let _ = self.players[me].inbox.send(text);
+    }
+
+    /// Interrupts the players picked out with a message, then asks again
+    /// whatever they were being asked.
+    fn tell(&self, msg: &str, to: impl Fn(&Player) -> bool) {
+        for player in self.players.iter().filter(|&player| to(player)) {
+            let _ = player.inbox.send(format!("\n{}\n{}", msg, self.prompt(player)));
+        }
+    }
+
+    fn prompt(&self, player: &Player) -> &'static str {
+        player.question.prompt(self.state.world, false)
+    }
+
+    fn remove(&mut self, me: usize, msg: &str) {
+        let player = self.players.remove(me);
+        for &obj in &player.carrying {
+            self.state.positions[obj] = player.here;
+        }
+        if player.gloved {
+            self.state.positions[GLOVES] = player.here;
+        }
+        self.tell(msg, |_| true);
+    }
+}
+
+/// AN OLD DIARY becomes THE OLD DIARY.
+fn the(name: &str) -> String {
+    let noun = name.strip_prefix("A ").or_else(|| name.strip_prefix("AN ")).unwrap_or(name);
+    format!("THE {}", noun)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::WALKTHROUGH;
+    use crate::{BOAT_OBJ, NORTH_BANK, STOCK};
+
+    fn seen(inbox: &Receiver<String>) -> String {
+        inbox.try_iter().collect()
+    }
+
+    #[test]
+    fn players_share_the_world_and_see_each_other() {
+        let shared = SharedWorld::new(&STOCK, false);
+        let (alice, alice_sees) = shared.join("alice").unwrap();
+        assert!(seen(&alice_sees).contains("IN YOUR LIVING ROOM."));
+        assert!(shared.join(" ALICE ").is_none());
+        let (bob, bob_sees) = shared.join("bob").unwrap();
+        assert!(seen(&alice_sees).contains("BOB HAS JOINED THE GAME."));
+        seen(&bob_sees);
+
+        assert!(shared.command(alice, "open box"));
+        assert!(shared.command(alice, "get bottle"));
+        assert!(seen(&bob_sees).contains("ALICE TAKES THE SMALL BOTTLE."));
+        assert!(shared.command(bob, "get bottle"));
+        assert!(seen(&bob_sees).contains("ALICE HAS IT!"));
+
+        assert!(shared.command(alice, "n"));
+        assert!(seen(&bob_sees).contains("ALICE LEAVES."));
+        assert!(shared.command(bob, "restart"));
+        assert!(seen(&bob_sees).contains("I DON'T KNOW HOW TO DO THAT."));
+        shared.leave(alice);
+        assert!(seen(&bob_sees).contains("ALICE HAS LEFT THE GAME."));
+        assert!(!shared.command(alice, "look"));
+    }
+
+    #[test]
+    fn quitting_asks_first_where_it_should() {
+        let shared = SharedWorld::new(&STOCK, true);
+        let (alice, alice_sees) = shared.join("alice").unwrap();
+        let (bob, bob_sees) = shared.join("bob").unwrap();
+        seen(&alice_sees);
+        assert!(shared.command(alice, "q"));
+        assert!(seen(&alice_sees).ends_with("ARE YOU SURE? "));
+        assert!(shared.command(bob, "n"));
+        assert!(seen(&alice_sees).ends_with("BOB LEAVES.\nARE YOU SURE? "));
+        assert!(shared.command(alice, "no"));
+        assert!(seen(&alice_sees).ends_with("\nWHAT NOW? "));
+        assert!(shared.command(alice, "quit"));
+        assert!(!shared.command(alice, "yes"));
+        assert!(seen(&bob_sees).contains("ALICE HAS LEFT THE GAME."));
+
+        let shared = SharedWorld::new(&STOCK, false);
+        let (alice, _alice_sees) = shared.join("alice").unwrap();
+        assert!(!shared.command(alice, "q"));
+    }
+
+    #[test]
+    fn the_boat_stays_while_anyone_is_in_it() {
+        let shared = SharedWorld::new(&STOCK, false);
+        let (alice, alice_sees) = shared.join("alice").unwrap();
+        for command in &WALKTHROUGH[..30] {
+            assert!(shared.command(alice, command));
+        }
+        assert!(seen(&alice_sees).ends_with("\nWHAT NOW? "));
+        assert_eq!(shared.lock().state.positions[BOAT_OBJ], immobile!(NORTH_BANK));
+
+        // the boat drifts back three turns after it's left empty
+        let (bob, _bob_sees) = shared.join("bob").unwrap();
+        for _ in 0..5 {
+            assert!(shared.command(bob, "look"));
+        }
+        assert_eq!(shared.lock().state.positions[BOAT_OBJ], immobile!(NORTH_BANK));
+        assert!(shared.command(alice, "leave boat"));
+        assert!(seen(&alice_sees).contains("NORTH BANK"));
+    }
+}
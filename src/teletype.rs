@@ -0,0 +1,124 @@
+//! Prints the game a character at a time, as fast as a modem of the day
+//! would have, with a blinking block cursor while it waits for a command.
+//! Optionally in upper case only, as on the 40-column home computers.
+//!
+//! Only a terminal gets the show. Printed to anything else, the text goes
+//! straight through, so transcripts and pipes are as fast as ever.
+
+use std::io::{self, IsTerminal};
+use std::thread;
+use std::time::Duration;
+
+use super::Terminal;
+
+/// A start bit, eight data bits and a stop bit.
+const BITS_PER_CHAR: u32 = 10;
+
+const BLINKING_BLOCK: &str = "\x1b[1 q";
+const USUAL_CURSOR: &str = "\x1b[0 q";
+
+/// Wraps another terminal, slowing down what's printed on it.
+pub struct Teletype<T: Terminal> {
+    inner: T,
+    /// How long each character takes at the baud rate. Zero when not a terminal.
+    delay: Duration,
+    upper: bool
+}
+
+impl<T: Terminal> Teletype<T> {
+    pub fn new(inner: T, baud: u32, upper: bool) -> Self {
+        let delay = if io::stdout().is_terminal() {
+            Duration::from_secs(BITS_PER_CHAR as u64) / baud.max(1)
+        } else {
+            Duration::ZERO
+        };
+        Teletype { inner, delay, upper }
+    }
+
+    fn shown(&self) -> bool {
+        !self.delay.is_zero()
+    }
+}
+
+impl<T: Terminal> Terminal for Teletype<T> {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        let mut rest = text;
+        while !rest.is_empty() {
+            // escape sequences go at once, and as they are
+            if let Some(escape) = rest.strip_prefix('\x1b') {
+                let len = match escape.strip_prefix('[') {
+                    Some(csi) => csi.find(|c: char| c.is_ascii_alphabetic() || c == '~').map_or(csi.len(), |end| end + 2),
+                    None => escape.chars().next().map_or(0, char::len_utf8)
+                };
+                self.inner.print(&rest[..1 + len])?;
+                rest = &rest[1 + len..];
+                continue;
+            }
+            let end = rest.find('\x1b').unwrap_or(rest.len());
+            let mut chars = rest[..end].to_string();
+            if self.upper {
+                chars.make_ascii_uppercase();
+            }
+            if self.shown() {
+                for c in chars.chars() {
+                    self.inner.print(c.encode_utf8(&mut [0; 4]))?;
+                    thread::sleep(self.delay);
+                }
+            } else {
+                self.inner.print(&chars)?;
+            }
+            rest = &rest[end..];
+        }
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        if !self.shown() {
+            return self.inner.read_line();
+        }
+        self.inner.print(BLINKING_BLOCK)?;
+        let line = self.inner.read_line();
+        self.inner.print(USUAL_CURSOR)?;
+        line
+    }
+
+    fn trace(&mut self, line: u32) {
+        self.inner.trace(line);
+    }
+
+    fn offer(&mut self, commands: &[String]) {
+        self.inner.offer(commands);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::Typist;
+
+    fn teletype(delay: Duration, upper: bool) -> Teletype<Typist> {
+        Teletype { inner: Typist::new(&["look"]), delay, upper }
+    }
+
+    #[test]
+    fn capitals_only_and_escapes_as_they_are() {
+        let mut plain = teletype(Duration::ZERO, true);
+        plain.print("you see \x1b[1ma box\x1b[0m.\x1b7\n").unwrap();
+        assert_eq!(plain.inner.printed, "YOU SEE \x1b[1mA BOX\x1b[0m.\x1b7\n");
+
+        let mut shown = teletype(Duration::from_nanos(1), false);
+        shown.print("a \x1b[2Kbox\x1b[").unwrap();
+        assert_eq!(shown.inner.printed, "a \x1b[2Kbox\x1b[");
+    }
+
+    #[test]
+    fn the_cursor_blinks_while_waiting() {
+        let mut shown = teletype(Duration::from_nanos(1), false);
+        assert_eq!(shown.read_line().unwrap().as_deref(), Some("look"));
+        assert_eq!(shown.inner.printed, format!("{}{}", BLINKING_BLOCK, USUAL_CURSOR));
+
+        let mut plain = teletype(Duration::ZERO, false);
+        plain.read_line().unwrap();
+        assert_eq!(plain.inner.printed, "");
+    }
+}
@@ -0,0 +1,160 @@
+//! Shows which lines of the book's program each command would have run,
+//! by quietly playing the same commands through the listing alongside.
+//!
+//! The lines are the original program's, so after a command the book
+//! doesn't know (SCORE, SAVE) the two games can drift apart.
+
+use std::io;
+use std::mem;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use super::{basic, Terminal};
+
+/// Wraps another terminal, printing the lines run before each response.
+pub struct Traced<T: Terminal> {
+    inner: T,
+    commands: Sender<String>,
+    traces: Receiver<Vec<u32>>,
+    ended: bool,
+    /// How wide to wrap the traces.
+    width: usize
+}
+
+/// The listing's side, running in the background.
+struct Shadow {
+    commands: Receiver<String>,
+    traces: Sender<Vec<u32>>,
+    lines: Vec<u32>
+}
+
+impl Terminal for Shadow {
+    fn print(&mut self, _text: &str) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        if self.traces.send(mem::take(&mut self.lines)).is_err() {
+            return Ok(None);
+        }
+        Ok(self.commands.recv().ok())
+    }
+
+    fn trace(&mut self, line: u32) {
+        self.lines.push(line);
+    }
+}
+
+impl<T: Terminal> Traced<T> {
+    pub fn new(inner: T, listing: String, width: usize) -> io::Result<Self> {
+        let (commands, shadow_commands) = mpsc::channel();
+        let (shadow_traces, traces) = mpsc::channel();
+        thread::spawn(move || {
+            let mut shadow = Shadow { commands: shadow_commands, traces: shadow_traces, lines: Vec::new() };
+            let _ = basic::run(&listing, &mut shadow);
+            let _ = shadow.traces.send(shadow.lines);
+        });
+        let mut traced = Traced { inner, commands, traces, ended: false, width };
+        traced.show_trace()?; // up to the first prompt
+        Ok(traced)
+    }
+
+    fn show_trace(&mut self) -> io::Result<()> {
+        let lines = match self.traces.recv() {
+            Ok(lines) => lines,
+            Err(_) => {
+                self.ended = true;
+                return self.inner.print("[THE BOOK'S PROGRAM HAS ENDED.]\n");
+            }
+        };
+        // loops would repeat themselves, so each line only once
+        let mut seen = Vec::new();
+        let mut text = String::from("[");
+        let mut column = 1;
+        for line in lines {
+            if seen.contains(&line) {
+                continue;
+            }
+            let number = line.to_string();
+            if !seen.is_empty() {
+                if column + 1 + number.len() >= self.width {
+                    text.push('\n');
+                    column = 0;
+                } else {
+                    text.push(' ');
+                    column += 1;
+                }
+            }
+            column += number.len();
+            text += &number;
+            seen.push(line);
+        }
+        text += "]\n";
+        self.inner.print(&text)
+    }
+}
+
+impl<T: Terminal> Terminal for Traced<T> {
+    fn print(&mut self, text: &str) -> io::Result<()> {
+        self.inner.print(text)
+    }
+
+    fn offer(&mut self, commands: &[String]) {
+        self.inner.offer(commands);
+    }
+
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let command = self.inner.read_line()?;
+        if let Some(command) = &command {
+            if !self.ended {
+                // it has stopped listening if it ended, which show_trace finds out
+                let _ = self.commands.send(command.clone());
+                self.show_trace()?;
+            }
+        }
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Types its lines and keeps what's printed.
+    struct Typist {
+        lines: Vec<&'static str>,
+        printed: String
+    }
+
+    impl Terminal for Typist {
+        fn print(&mut self, text: &str) -> io::Result<()> {
+            self.printed += text;
+            Ok(())
+        }
+
+        fn read_line(&mut self) -> io::Result<Option<String>> {
+            Ok(if self.lines.is_empty() { None } else { Some(self.lines.remove(0).to_string()) })
+        }
+    }
+    use crate::wrap::BOOK_WIDTH;
+
+    fn traced(listing: &str, lines: &[&'static str], width: usize) -> String {
+        let typist = Typist { lines: lines.to_vec(), printed: String::new() };
+        let mut traced = Traced::new(typist, listing.to_string(), width).unwrap();
+        while traced.read_line().unwrap().is_some() {}
+        traced.inner.printed
+    }
+
+    #[test]
+    fn shows_each_line_run_once() {
+        let listing = "10 PRINT 1\n20 INPUT A$\n30 IF A$=\"Q\" THEN END\n40 GOTO 20\n";
+        assert_eq!(traced(listing, &["A", "Q", "Z"], BOOK_WIDTH), "[10 20]\n[30 40 20]\n[30]\n[THE BOOK'S PROGRAM HAS ENDED.]\n");
+    }
+
+    #[test]
+    fn wraps_long_traces() {
+        let listing: String = (1..=12).map(|i| format!("{} REM\n", i * 1000)).collect::<String>() + "13000 INPUT A$\n";
+        assert_eq!(traced(&listing, &[], BOOK_WIDTH), "[1000 2000 3000 4000 5000 6000 7000\n8000 9000 10000 11000 12000 13000]\n");
+        assert_eq!(traced(&listing, &[], 80), "[1000 2000 3000 4000 5000 6000 7000 8000 9000 10000 11000 12000 13000]\n");
+    }
+}
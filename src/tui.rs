@@ -0,0 +1,148 @@
+//! A full-screen front end: a status line with the room, moves and score,
+//! a pane with the exits and what's here, and the game scrolling below.
+//! It's all ANSI escapes, so any terminal since the VT100 will do.
+
+use std::io::{self, IsTerminal};
+use std::process::{self, Command};
+
+use super::prose;
+use super::wrap::lines;
+use super::{play as play_plainly, Fix, Session, Terminal, World};
+
+/// Rows above the transcript: the status line, the exits, what's here, and a rule.
+const PANE: usize = 6;
+
+/// Lines the pane can give to what's here.
+const SEEN: usize = PANE - 3;
+
+/// Rows and columns, from stty, since std can't ask.
+pub fn size() -> Option<(usize, usize)> {
+    let size = stty(&["size"])?;
+    let (rows, columns) = size.trim().split_once(' ')?;
+    Some((rows.parse().ok()?, columns.parse().ok()?))
+}
+
+/// Runs stty on the terminal we're reading from, for what it prints.
+pub(crate) fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty").args(args).stdin(process::Stdio::inherit()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Plays full screen, or plainly when stdout isn't a terminal or is too small.
+/// Either way the text is wrapped to `width`, or the screen if it's narrower,
+/// and in modern prose if `modern`.
+pub fn play(
+    term: &mut dyn Terminal, world: &'static World, confirm: bool, width: usize, modern: bool
+) -> io::Result<()> {
+    let (rows, columns) = match size() {
+        Some((rows, columns)) if io::stdout().is_terminal() && rows > PANE + 2 && columns >= 40 => {
+            (rows, columns)
+        }
+        _ => return play_plainly(term, world, confirm, width, modern)
+    };
+    let mut session = Session::new(world, confirm);
+    session.set_width(width.min(columns));
+    session.set_modern(modern);
+    // clear, keep the transcript below the pane, and start it at the bottom
+    term.print(&format!("\x1b[2J\x1b[{};{}r\x1b[{};1H", PANE + 1, rows, rows))?;
+    let played = take_turns(term, &mut session, columns, modern);
+    // the whole screen scrolls again
+    term.print(&format!("\x1b[r\x1b[{};1H\n", rows))?;
+    played
+}
+
+fn take_turns(term: &mut dyn Terminal, session: &mut Session, columns: usize, modern: bool) -> io::Result<()> {
+    let mut text = session.take_output();
+    loop {
+        draw(term, session, columns, modern)?;
+        term.print(&text)?;
+        term.print(session.prompt())?;
+        if session.is_over() {
+            return Ok(());
+        }
+        term.offer(&session.completions());
+        match term.read_line()? {
+            Some(input) => text = session.step(&input),
+            None => return Ok(())
+        }
+    }
+}
+
+fn draw(term: &mut dyn Terminal, session: &Session, columns: usize, modern: bool) -> io::Result<()> {
+    let say = |text: &str| if modern { prose::translate(text) } else { text.to_string() };
+    let world = session.world();
+    let mut room = say(world.rooms[session.here() as usize].desc);
+    // "in your living room." on its own wants a capital
+    if let Some(first) = room.get_mut(..1) {
+        first.make_ascii_uppercase();
+    }
+    let room = room.trim_end_matches('.');
+    let mut counts = format!("MOVES: {}", session.moves());
+    if world.fixed(Fix::Score) {
+        counts += &format!("  SCORE: {}", session.score());
+    }
+    // two spaces at least between the room and the counts, and cut short on a narrow screen
+    let room: String = room.chars().take(columns.saturating_sub(counts.len() + 4)).collect();
+    let status = format!(" {:width$}{} ", room, counts, width = columns.saturating_sub(counts.len() + 2));
+    let status: String = status.chars().take(columns).collect();
+
+    let exits = say(&format!("YOU CAN GO: {}", session.exits().join(" ")));
+    let names: Vec<String> = session.objects_here().iter().map(|&id| say(world.objects[id].name)).collect();
+    let seen = if names.is_empty() { say("NOTHING OF INTEREST") } else { names.join(", ") };
+    let mut seen = lines(&format!("{} {}", say("YOU CAN SEE:"), seen), columns);
+    if seen.len() > SEEN {
+        seen.truncate(SEEN);
+        seen[SEEN - 1] = format!("{:.*}...", columns.saturating_sub(3), seen[SEEN - 1]);
+    }
+
+    // the cursor is saved and put back, so the transcript carries on where it was
+    let mut screen = format!("\x1b7\x1b[1;1H\x1b[7m{}\x1b[0m", status);
+    let lines = std::iter::once(&exits).chain(&seen).map(String::as_str);
+    let blanks = std::iter::repeat_n("", SEEN - seen.len());
+    for (row, line) in lines.chain(blanks).enumerate() {
+        screen += &format!("\x1b[{};1H\x1b[2K{}", row + 2, line);
+    }
+    screen += &format!("\x1b[{};1H{}\x1b8", PANE, "-".repeat(columns));
+    term.print(&screen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::Typist;
+    use crate::STOCK;
+
+    #[test]
+    fn the_pane_shows_the_room_exits_and_what_is_here() {
+        let mut typist = Typist::new(&["OPEN BOX"]);
+        let mut session = Session::new(&STOCK, false);
+        take_turns(&mut typist, &mut session, 40, false).unwrap();
+        let screen = typist.printed;
+        assert!(screen.contains("\x1b[7m IN YOUR LIVING ROO  MOVES: 0  SCORE: 0 \x1b[0m"));
+        assert!(screen.contains("\x1b[7m IN YOUR LIVING ROO  MOVES: 1  SCORE: 0 \x1b[0m"));
+        assert!(screen.contains("\x1b[2;1H\x1b[2KYOU CAN GO: NORTH SOUTH EAST"));
+        assert!(screen.contains("\x1b[3;1H\x1b[2KYOU CAN SEE: AN OLD DIARY, A SMALL BOX\x1b[4;1H"));
+        assert!(screen.contains(&format!("\x1b[{};1H{}\x1b8", PANE, "-".repeat(40))));
+        assert!(screen.contains("\x1b[4;1H\x1b[2KA SMALL BOTTLE\x1b[5;1H"));
+        assert!(screen.contains("SOMETHING FELL OUT!"));
+    }
+
+    #[test]
+    fn the_status_line_fits_however_narrow_the_screen() {
+        let session = Session::new(&STOCK, false);
+        let status = |columns| {
+            let mut typist = Typist::new(&[]);
+            draw(&mut typist, &session, columns, false).unwrap();
+            let screen = typist.printed;
+            let start = screen.find("\x1b[7m").unwrap() + 4;
+            screen[start..screen.find("\x1b[0m").unwrap()].to_string()
+        };
+        assert_eq!(status(80), format!(" IN YOUR LIVING ROOM{}MOVES: 0  SCORE: 0 ", " ".repeat(41)));
+        assert_eq!(status(24), " IN  MOVES: 0  SCORE: 0 ");
+        assert_eq!(status(12), " MOVES: 0  S");
+        assert_eq!(status(0), "");
+    }
+}
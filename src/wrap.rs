@@ -0,0 +1,81 @@
+//! Lays the game's text out to a width: the book's 40 columns, 80, or
+//! however wide the terminal is.
+//!
+//! The book broke its longer messages by hand to fit the screen. Here
+//! they're single messages with a `BREAK` where the book broke them, so
+//! they can be reflowed to any width, or printed just as the book had them.
+
+/// Where the book broke a message across lines.
+pub const BREAK: char = '|';
+
+/// The home computers the book was written for.
+pub const BOOK_WIDTH: usize = 40;
+
+/// Breaks each line longer than `width` between words. Indented lines
+/// carry on at the same indent.
+pub fn wrap(text: &str, width: usize) -> String {
+    let text = text.replace(BREAK, " ");
+    let lines: Vec<String> = text.split('\n').flat_map(|line| lines(line, width)).collect();
+    lines.join("\n")
+}
+
+/// The text as the book printed it, with its own line breaks.
+pub fn as_printed(text: &str) -> String {
+    text.replace(BREAK, "\n")
+}
+
+/// One line broken between words into lines no wider than `width`,
+/// except for words that are wider on their own.
+pub fn lines(line: &str, width: usize) -> Vec<String> {
+    let words = line.trim_start_matches(' ');
+    let indent = &line[..line.len() - words.len()];
+    let mut lines = vec![indent.to_string()];
+    for word in words.split(' ') {
+        let line = lines.last_mut().unwrap();
+        if line.len() > indent.len() && line.len() + 1 + word.len() > width {
+            lines.push(format!("{}{}", indent, word));
+        } else {
+            if line.len() > indent.len() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Session, STOCK};
+
+    #[test]
+    fn lines_break_between_words() {
+        assert_eq!(lines("THE QUICK BROWN FOX", 10), ["THE QUICK", "BROWN FOX"]);
+        assert_eq!(lines("    A SMALL BOTTLE", 12), ["    A SMALL", "    BOTTLE"]);
+        assert_eq!(lines("A SUPERCALIFRAGILISTIC WORD", 8), ["A", "SUPERCALIFRAGILISTIC", "WORD"]);
+        assert_eq!(lines("", 8), [""]);
+    }
+
+    #[test]
+    fn breaks_are_reflowed_or_kept_as_printed() {
+        let text = "IT'S A LONG|WAY DOWN.\nTHE END.";
+        assert_eq!(wrap(text, 40), "IT'S A LONG WAY DOWN.\nTHE END.");
+        assert_eq!(wrap(text, 12), "IT'S A LONG\nWAY DOWN.\nTHE END.");
+        assert_eq!(as_printed(text), "IT'S A LONG\nWAY DOWN.\nTHE END.");
+    }
+
+    #[test]
+    fn the_game_is_wrapped_to_its_width() {
+        let mut session = Session::new(&STOCK, false);
+        session.set_width(80);
+        let intro = session.take_output();
+        assert!(intro.contains("\nALL YOUR LIFE YOU HAD HEARD THE STORIES ABOUT YOUR CRAZY UNCLE SIMON. HE WAS AN\n"));
+        assert!(intro.lines().all(|line| line.len() <= 80));
+
+        let mut session = Session::new(STOCK.as_printed(), false);
+        session.set_width(80);
+        let intro = session.take_output();
+        assert!(intro.contains("\nALL YOUR LIFE YOU HAD HEARD THE STORIES\nABOUT YOUR CRAZY UNCLE SIMON. HE WAS AN\n"));
+    }
+}
@@ -0,0 +1,1565 @@
+//! Compiles a world into a version 3 Z-machine story file, playable in
+//! Frotz, Gargoyle and the other standard interpreters.
+//!
+//! The story plays like the listing `export` writes: the same three-letter
+//! parser, the same verbs and, for worlds that have them, the same puzzles.
+//! Of our additions it keeps the score, which the interpreter shows on the
+//! status line, and SAVE, RESTORE and RESTART, which the Z-machine does for
+//! us. The daemons, NPCs and dying stay behind.
+//!
+//! Words are told apart by their first three letters, so the story's
+//! dictionary is empty and its parser reads the typed text itself.
+
+use super::wrap::as_printed;
+use super::{
+    Fix, World, BOAT_OBJ, BOAT_ROOM, BOTTLE, CASTLE_GATE, FOREST_EDGE, GARAGE, GLOVES, GUARD,
+    GUARDED, INVENTORY, LADDER, MAX_INVENTORY, MAX_SCORE, NARROW_HALL, NORTH_BANK, OPEN_FIELD,
+    RANKS, RUBY, SALT, SOUTH_BANK, START_ROOM, SWORD, TREE_BRANCH, TREE_TOP
+};
+
+#[derive(Clone, Copy)]
+enum Operand {
+    Num(u16),
+    Var(u8),
+    /// The packed address of a routine, filled in once the code is laid out.
+    Routine(Label)
+}
+
+use self::Operand::*;
+
+type Label = usize;
+
+/// What to fill in once the code is laid out.
+enum Patch {
+    Branch(Label),
+    Jump(Label),
+    Routine(Label)
+}
+
+/// Variable 0 is the top of the stack, 1 to 15 a routine's locals.
+const SP: u8 = 0;
+
+// Globals. The first three are shown on the status line.
+const HERE: u8 = 0x10;
+const SCORE: u8 = 0x11;
+const MOVES: u8 = 0x12;
+const VERB: u8 = 0x13;
+const NOUN: u8 = 0x14;
+const WORDS: u8 = 0x15;
+const OBJ: u8 = 0x16;
+const DIR: u8 = 0x17;
+const WON: u8 = 0x18;
+const SALTED: u8 = 0x19;
+const FORMULATED: u8 = 0x1a;
+const GLOVED: u8 = 0x1b;
+
+/// Longest input line, and most words in it.
+const MAX_INPUT: usize = 100;
+const MAX_WORDS: usize = 10;
+
+/// Alphabet A2 of version 3, from z-character 8 on.
+const A2: &str = "0123456789.,!?_#'\"/\\-:()";
+
+/// Packs text into z-characters, three to a word.
+fn encode(text: &str) -> Vec<u8> {
+    let mut zchars = Vec::new();
+    for c in text.chars() {
+        match c {
+            ' ' => zchars.push(0),
+            'a'..='z' => zchars.push(c as u8 - b'a' + 6),
+            'A'..='Z' => zchars.extend([4, c as u8 - b'A' + 6]),
+            '\n' => zchars.extend([5, 7]),
+            _ => match A2.find(c) {
+                Some(i) => zchars.extend([5, i as u8 + 8]),
+                None => {
+                    let zscii = if c.is_ascii() { c as u8 } else { b'?' };
+                    zchars.extend([5, 6, zscii >> 5, zscii & 31]);
+                }
+            }
+        }
+    }
+    while zchars.is_empty() || !zchars.len().is_multiple_of(3) {
+        zchars.push(5);
+    }
+    let mut bytes = Vec::new();
+    for triple in zchars.chunks(3) {
+        let word = (triple[0] as u16) << 10 | (triple[1] as u16) << 5 | triple[2] as u16;
+        bytes.extend(word.to_be_bytes());
+    }
+    let last = bytes.len() - 2;
+    bytes[last] |= 0x80;
+    bytes
+}
+
+/// A word's first three letters as a number, the way the story's parser works
+/// it out: a to z are 1 to 26 and anything else 31, in base 32.
+fn code(word: &str) -> u16 {
+    word.chars().take(3).fold(0, |code, c| {
+        let c = c.to_ascii_lowercase();
+        code * 32 + if c.is_ascii_lowercase() { c as u16 - 'a' as u16 + 1 } else { 31 }
+    })
+}
+
+/// What the branch instructions can test.
+enum Test {
+    Eq(Operand, Operand),
+    Ne(Operand, Operand),
+    Lt(Operand, Operand),
+    Gt(Operand, Operand),
+    Zero(Operand),
+    NonZero(Operand)
+}
+
+use self::Test::*;
+
+/// Assembles the routines that go in high memory.
+struct Asm {
+    code: Vec<u8>,
+    labels: Vec<Option<usize>>,
+    patches: Vec<(usize, Patch)>,
+    /// The strings the tables point at, which go after the code.
+    strings: Vec<Vec<u8>>
+}
+
+impl Asm {
+    fn label(&mut self) -> Label {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: Label) {
+        self.labels[label] = Some(self.code.len());
+    }
+
+    /// Starts a routine, which has to be at an even address.
+    fn routine(&mut self, label: Label, locals: u8) {
+        if self.code.len() % 2 == 1 {
+            self.code.push(0);
+        }
+        self.place(label);
+        self.code.push(locals);
+        self.code.extend(vec![0; 2 * locals as usize]);
+    }
+
+    fn text(&mut self, text: &str) -> usize {
+        self.strings.push(encode(text));
+        self.strings.len() - 1
+    }
+
+    fn operand_type(operand: Operand) -> u8 {
+        match operand {
+            Num(n) if n < 256 => 1,
+            Var(_) => 2,
+            _ => 0
+        }
+    }
+
+    fn operand(&mut self, operand: Operand) {
+        match operand {
+            Num(n) if n < 256 => self.code.push(n as u8),
+            Num(n) => self.code.extend(n.to_be_bytes()),
+            Var(v) => self.code.push(v),
+            Routine(label) => {
+                self.patches.push((self.code.len(), Patch::Routine(label)));
+                self.code.extend([0, 0]);
+            }
+        }
+    }
+
+    /// The variable form, which every 2OP and VAR instruction can use.
+    fn var_form(&mut self, opcode: u8, operands: &[Operand]) {
+        self.code.push(opcode);
+        let mut types = 0xff;
+        for (i, &operand) in operands.iter().enumerate() {
+            let shift = 6 - 2 * i;
+            types = types & !(3 << shift) | Self::operand_type(operand) << shift;
+        }
+        self.code.push(types);
+        for &operand in operands {
+            self.operand(operand);
+        }
+    }
+
+    fn op2(&mut self, opcode: u8, operands: &[Operand]) {
+        self.var_form(0xc0 | opcode, operands);
+    }
+
+    fn var(&mut self, opcode: u8, operands: &[Operand]) {
+        self.var_form(0xe0 | opcode, operands);
+    }
+
+    fn op1(&mut self, opcode: u8, operand: Operand) {
+        self.code.push(0x80 | Self::operand_type(operand) << 4 | opcode);
+        self.operand(operand);
+    }
+
+    fn op0(&mut self, opcode: u8) {
+        self.code.push(0xb0 | opcode);
+    }
+
+    fn store(&mut self, var: u8) {
+        self.code.push(var);
+    }
+
+    /// Always the long form, so it can reach anywhere.
+    fn branch(&mut self, on: bool, to: Label) {
+        self.patches.push((self.code.len(), Patch::Branch(to)));
+        self.code.extend([if on { 0x80 } else { 0 }, 0]);
+    }
+
+    fn test(&mut self, test: Test, on: bool, to: Label) {
+        let on = match test {
+            Eq(a, b) => { self.op2(0x01, &[a, b]); on }
+            Ne(a, b) => { self.op2(0x01, &[a, b]); !on }
+            Lt(a, b) => { self.op2(0x02, &[a, b]); on }
+            Gt(a, b) => { self.op2(0x03, &[a, b]); on }
+            Zero(a) => { self.op1(0x00, a); on }
+            NonZero(a) => { self.op1(0x00, a); !on }
+        };
+        self.branch(on, to);
+    }
+
+    /// Runs `body` only when the test passes.
+    fn when(&mut self, test: Test, body: impl FnOnce(&mut Self)) {
+        let skip = self.label();
+        self.test(test, false, skip);
+        body(self);
+        self.place(skip);
+    }
+
+    /// Runs `body` with the variable going from `from` to `to`.
+    fn for_each(&mut self, var: u8, from: u16, to: u16, body: impl FnOnce(&mut Self)) {
+        if from > to {
+            return;
+        }
+        self.set(var, Num(from));
+        let top = self.label();
+        self.place(top);
+        body(self);
+        self.op2(0x05, &[Num(var as u16), Num(to)]); // inc_chk
+        self.branch(false, top);
+    }
+
+    fn jump(&mut self, to: Label) {
+        self.code.push(0x8c);
+        self.patches.push((self.code.len(), Patch::Jump(to)));
+        self.code.extend([0, 0]);
+    }
+
+    fn set(&mut self, var: u8, value: Operand) {
+        self.op2(0x0d, &[Num(var as u16), value]);
+    }
+
+    fn add(&mut self, a: Operand, b: Operand, to: u8) {
+        self.op2(0x14, &[a, b]);
+        self.store(to);
+    }
+
+    fn mul(&mut self, a: Operand, b: Operand, to: u8) {
+        self.op2(0x16, &[a, b]);
+        self.store(to);
+    }
+
+    fn and(&mut self, a: Operand, b: Operand, to: u8) {
+        self.op2(0x09, &[a, b]);
+        self.store(to);
+    }
+
+    fn loadb(&mut self, array: Operand, index: Operand, to: u8) {
+        self.op2(0x10, &[array, index]);
+        self.store(to);
+    }
+
+    fn loadw(&mut self, array: Operand, index: Operand, to: u8) {
+        self.op2(0x0f, &[array, index]);
+        self.store(to);
+    }
+
+    fn storeb(&mut self, array: Operand, index: Operand, value: Operand) {
+        self.var(0x02, &[array, index, value]);
+    }
+
+    fn inc(&mut self, var: u8) {
+        self.op1(0x05, Num(var as u16));
+    }
+
+    fn call(&mut self, routine: Label, args: &[Operand], to: u8) {
+        let mut operands = vec![Routine(routine)];
+        operands.extend_from_slice(args);
+        self.var(0x00, &operands);
+        self.store(to);
+    }
+
+    /// Calls a routine for what it does rather than what it returns.
+    fn run(&mut self, routine: Label) {
+        self.call(routine, &[], SP);
+        self.op0(0x09); // pop
+    }
+
+    fn ret(&mut self, value: Operand) {
+        self.op1(0x0b, value);
+    }
+
+    fn rtrue(&mut self) {
+        self.op0(0x00);
+    }
+
+    fn save(&mut self, on: bool, to: Label) {
+        self.op0(0x05);
+        self.branch(on, to);
+    }
+
+    fn restore(&mut self, on: bool, to: Label) {
+        self.op0(0x06);
+        self.branch(on, to);
+    }
+
+    fn restart(&mut self) {
+        self.op0(0x07);
+    }
+
+    fn quit(&mut self) {
+        self.op0(0x0a);
+    }
+
+    fn read(&mut self, text: u16, parse: u16) {
+        self.var(0x04, &[Num(text), Num(parse)]);
+    }
+
+    fn print(&mut self, text: &str) {
+        self.op0(0x02);
+        self.code.extend(encode(text));
+    }
+
+    /// Prints the text and a line break, and returns true.
+    fn print_ret(&mut self, text: &str) {
+        self.op0(0x03);
+        self.code.extend(encode(text));
+    }
+
+    fn print_paddr(&mut self, addr: Operand) {
+        self.op1(0x0d, addr);
+    }
+
+    fn print_num(&mut self, n: Operand) {
+        self.var(0x06, &[n]);
+    }
+
+    fn new_line(&mut self) {
+        self.op0(0x0b);
+    }
+
+    /// Lays the code out at `base`, with the strings after it,
+    /// and returns it along with the strings' packed addresses.
+    fn link(mut self, base: usize) -> (Vec<u8>, Vec<u16>) {
+        let mut packed = Vec::new();
+        for string in &self.strings {
+            if self.code.len() % 2 == 1 {
+                self.code.push(0);
+            }
+            packed.push(((base + self.code.len()) / 2) as u16);
+            self.code.extend(string);
+        }
+        for (at, patch) in &self.patches {
+            let target = |label: Label| self.labels[label].expect("label never placed");
+            let value = match *patch {
+                // both count from the end of the instruction, less 2
+                Patch::Branch(label) => {
+                    let offset = (target(label) as isize - *at as isize) as u16 & 0x3fff;
+                    offset | (self.code[*at] as u16 & 0x80) << 8
+                }
+                Patch::Jump(label) => (target(label) as isize - *at as isize) as u16,
+                Patch::Routine(label) => ((base + target(label)) / 2) as u16
+            };
+            self.code[*at..*at + 2].copy_from_slice(&value.to_be_bytes());
+        }
+        (self.code, packed)
+    }
+}
+
+pub fn compile(world: &World) -> Vec<u8> {
+    let mut story = vec![0; 0x40];
+
+    // no abbreviations, so they all point at one empty string
+    let abbreviations = story.len();
+    let empty = abbreviations + 96 * 2;
+    for _ in 0..96 {
+        story.extend(((empty / 2) as u16).to_be_bytes());
+    }
+    story.extend(encode(""));
+
+    // an object for each room, for the status line to name
+    let objects = story.len();
+    story.extend([0; 31 * 2]);
+    let entries = story.len();
+    story.extend(vec![0; 9 * (world.rooms.len() - 1)]);
+    for (i, room) in world.rooms[1..].iter().enumerate() {
+        let props = (story.len() as u16).to_be_bytes();
+        story[entries + 9 * i + 7..entries + 9 * i + 9].copy_from_slice(&props);
+        let name = encode(room.desc.trim_end_matches('.'));
+        story.push((name.len() / 2) as u8);
+        story.extend(name);
+        story.push(0);
+    }
+
+    let globals = story.len();
+    story.extend([0; 240 * 2]);
+
+    let positions = story.len();
+    story.extend(world.objects.iter().map(|obj| obj.start));
+    let text = story.len();
+    story.push(MAX_INPUT as u8);
+    story.extend([0; MAX_INPUT + 1]);
+    let parse = story.len();
+    story.push(MAX_WORDS as u8);
+    story.extend([0; 1 + 4 * MAX_WORDS]);
+
+    let static_base = story.len();
+    let exits = story.len();
+    for room in world.rooms {
+        story.extend([room.n, room.s, room.e, room.w, room.u, room.d]);
+    }
+    let tags = story.len();
+    for obj in world.objects {
+        story.extend(code(obj.tag).to_be_bytes());
+    }
+    // packed addresses, filled in once the strings are laid out
+    let descs = story.len();
+    story.extend(vec![0; 2 * world.rooms.len()]);
+    let names = story.len();
+    story.extend(vec![0; 2 * world.objects.len()]);
+    let dictionary = story.len();
+    story.extend([0, 7, 0, 0]); // no separators, no words
+
+    if story.len() % 2 == 1 {
+        story.push(0);
+    }
+    let high_base = story.len();
+
+    let mut asm = Asm { code: Vec::new(), labels: Vec::new(), patches: Vec::new(), strings: Vec::new() };
+    let desc_texts: Vec<usize> = world.rooms.iter().map(|room| asm.text(room.desc)).collect();
+    let name_texts: Vec<usize> = world.objects.iter().map(|obj| asm.text(obj.name)).collect();
+    let tables = Tables {
+        positions: positions as u16,
+        text: text as u16,
+        parse: parse as u16,
+        exits: exits as u16,
+        tags: tags as u16,
+        descs: descs as u16,
+        names: names as u16
+    };
+    let (code, packed) = Game::new(world, tables, asm).emit().link(high_base);
+    story.extend(code);
+
+    for (table, texts) in [(descs, desc_texts), (names, name_texts)] {
+        for (i, text) in texts.into_iter().enumerate() {
+            story[table + 2 * i..table + 2 * i + 2].copy_from_slice(&packed[text].to_be_bytes());
+        }
+    }
+
+    story[0] = 3;
+    let mut header = |at: usize, value: usize| {
+        story[at..at + 2].copy_from_slice(&(value as u16).to_be_bytes());
+    };
+    header(0x02, 1); // release
+    header(0x04, high_base);
+    header(0x06, high_base); // where it starts, calling main
+    header(0x08, dictionary);
+    header(0x0a, objects);
+    header(0x0c, globals);
+    header(0x0e, static_base);
+    header(0x18, abbreviations);
+    story[0x12..0x18].copy_from_slice(b"860101"); // serial, the date
+    if story.len() % 2 == 1 {
+        story.push(0);
+    }
+    let length = (story.len() / 2) as u16;
+    let checksum = story[0x40..].iter().fold(0u16, |sum, &b| sum.wrapping_add(b as u16));
+    story[0x1a..0x1c].copy_from_slice(&length.to_be_bytes());
+    story[0x1c..0x1e].copy_from_slice(&checksum.to_be_bytes());
+    story
+}
+
+/// Where the tables are, in dynamic memory and then static.
+#[derive(Clone, Copy)]
+struct Tables {
+    /// A byte per object, like the port's positions.
+    positions: u16,
+    text: u16,
+    parse: u16,
+    /// Six bytes per room.
+    exits: u16,
+    /// A word per object, its tag's code.
+    tags: u16,
+    /// Packed addresses of the rooms' descriptions and the objects' names.
+    descs: u16,
+    names: u16
+}
+
+/// The story's routines, mostly one for each subroutine or verb of the listing.
+#[derive(Clone, Copy)]
+struct Routines {
+    main: Label,
+    read: Label,
+    word: Label,
+    sure: Label,
+    look: Label,
+    list_exits: Label,
+    list_items: Label,
+    lookup: Label,
+    here: Label,
+    count: Label,
+    go: Label,
+    inventory: Label,
+    get: Label,
+    drop: Label,
+    examine: Label,
+    score: Label,
+    end: Label,
+    open: Label,
+    read_obj: Label,
+    pour: Label,
+    poured: Label,
+    climb: Label,
+    jump: Label,
+    dig: Label,
+    row: Label,
+    wave: Label,
+    leave: Label,
+    fight: Label,
+    wear: Label
+}
+
+struct Game<'a> {
+    world: &'a World,
+    t: Tables,
+    r: Routines,
+    asm: Asm
+}
+
+/// Awards points towards the score, if we're keeping one.
+fn award(a: &mut Asm, world: &World, points: u16) {
+    if world.fixed(Fix::Score) {
+        a.add(Var(SCORE), Num(points), SCORE);
+    }
+}
+
+/// Asks for the noun the verb needs, if there isn't one.
+fn want(a: &mut Asm, verb: &str) {
+    a.when(Eq(Var(WORDS), Num(1)), |a| a.print_ret(&format!("WHAT DO YOU WANT TO {}?", verb)));
+}
+
+fn is_noun(tag: &str) -> Test {
+    Eq(Var(NOUN), Num(code(tag)))
+}
+
+/// Line 1100's check on the noun, with THAT ISN'T HERE! if it fails.
+fn need_here(a: &mut Asm, r: Routines) {
+    a.call(r.lookup, &[], OBJ);
+    a.call(r.here, &[], SP);
+    a.when(Zero(Var(SP)), |a| a.print_ret("THAT ISN'T HERE!"));
+}
+
+/// Whether a given object is here, on the stack.
+fn here(a: &mut Asm, r: Routines, obj: u16) -> Operand {
+    a.set(OBJ, Num(obj));
+    a.call(r.here, &[], SP);
+    Var(SP)
+}
+
+/// The fifth letter of a one-word command, to tell RESTART from RESTORE.
+fn fifth_letter(a: &mut Asm, t: Tables, to: u8) {
+    a.loadb(Num(t.parse), Num(5), to); // where the first word starts
+    a.add(Num(t.text), Var(to), to);
+    a.loadb(Var(to), Num(4), to);
+}
+
+impl<'a> Game<'a> {
+    fn new(world: &'a World, t: Tables, mut asm: Asm) -> Self {
+        let mut label = || asm.label();
+        let r = Routines {
+            main: label(), read: label(), word: label(), sure: label(), look: label(),
+            list_exits: label(), list_items: label(), lookup: label(), here: label(),
+            count: label(), go: label(), inventory: label(), get: label(), drop: label(),
+            examine: label(), score: label(), end: label(), open: label(), read_obj: label(),
+            pour: label(), poured: label(), climb: label(), jump: label(), dig: label(),
+            row: label(), wave: label(), leave: label(), fight: label(), wear: label()
+        };
+        Game { world, t, r, asm }
+    }
+
+    fn emit(mut self) -> Asm {
+        // the first instruction isn't in a routine
+        self.asm.call(self.r.main, &[], SP);
+        self.asm.quit();
+
+        self.main();
+        self.input();
+        self.look();
+        self.subroutines();
+        self.go();
+        self.generic_verbs();
+        self.end();
+        if self.world.puzzles {
+            self.puzzles();
+        }
+        self.asm
+    }
+
+    /// The main loop, and line 100's parser.
+    fn main(&mut self) {
+        let (world, r, t) = (self.world, self.r, self.t);
+        let a = &mut self.asm;
+        a.routine(r.main, 2);
+        let (dir_word, ch) = (1, 2);
+        if !world.intro.is_empty() {
+            a.print(&format!("{}\n", as_printed(world.intro)));
+        }
+        a.set(HERE, Num(START_ROOM as u16));
+        a.run(r.look);
+
+        // commands go on to `acted` to take a move, or straight to `next` if they take none
+        let (top, acted, next, dunno) = (a.label(), a.label(), a.label(), a.label());
+        a.place(top);
+        a.new_line();
+        a.print("WHAT NOW? ");
+        a.run(r.read);
+        a.test(Zero(Var(WORDS)), true, top); // not worth a turn
+
+        // going somewhere? (GO optional)
+        a.set(dir_word, Var(VERB));
+        a.when(Eq(Var(VERB), Num(code("GO"))), |a| {
+            a.when(Gt(Var(WORDS), Num(1)), |a| a.set(dir_word, Var(NOUN)));
+        });
+        a.set(DIR, Num(0));
+        let dirs = [["N", "NOR"], ["S", "SOU"], ["E", "EAS"], ["W", "WES"], ["U", "UP"], ["D", "DOW"], ["BOA", "BOA"]];
+        for (i, words) in dirs.iter().enumerate() {
+            for word in words {
+                a.when(Eq(Var(dir_word), Num(code(word))), |a| a.set(DIR, Num(i as u16 + 1)));
+            }
+        }
+        a.when(NonZero(Var(DIR)), |a| {
+            a.run(r.go);
+            a.jump(acted);
+        });
+        a.when(is_noun("SHA"), |a| a.set(NOUN, Num(code("SAL")))); // Line 210
+        a.when(is_noun("FOR"), |a| a.set(NOUN, Num(code("BOT")))); // 220
+        a.test(Gt(Var(WORDS), Num(2)), true, dunno);
+
+        let verb = |a: &mut Asm, words: &[&str], alone: bool, routine: Label| {
+            let (run, skip) = (a.label(), a.label());
+            if alone {
+                a.test(Ne(Var(WORDS), Num(1)), true, skip);
+            }
+            for word in words {
+                a.test(Eq(Var(VERB), Num(code(word))), true, run);
+            }
+            a.jump(skip);
+            a.place(run);
+            a.run(routine);
+            a.jump(acted);
+            a.place(skip);
+        };
+
+        a.when(Eq(Var(WORDS), Num(1)), |a| {
+            let quit = a.label();
+            a.test(Eq(Var(VERB), Num(code("Q"))), true, quit);
+            a.when(Eq(Var(VERB), Num(code("QUI"))), |a| {
+                a.place(quit);
+                a.call(r.sure, &[], SP);
+                a.test(Zero(Var(SP)), true, next);
+                a.quit();
+            });
+            a.when(Eq(Var(VERB), Num(code("RES"))), |a| {
+                fifth_letter(a, t, ch);
+                if world.fixed(Fix::Restart) {
+                    a.when(Eq(Var(ch), Num(b'a' as u16)), |a| {
+                        a.call(r.sure, &[], SP);
+                        a.test(Zero(Var(SP)), true, next);
+                        a.restart();
+                    });
+                }
+                if world.fixed(Fix::Save) {
+                    a.when(Eq(Var(ch), Num(b'o' as u16)), |a| {
+                        let restored = a.label();
+                        a.restore(true, restored);
+                        a.print("THERE'S NO SAVED GAME TO RESTORE.\n");
+                        a.place(restored);
+                        a.jump(next);
+                    });
+                }
+            });
+            if world.fixed(Fix::Save) {
+                a.when(Eq(Var(VERB), Num(code("SAV"))), |a| {
+                    // a restored game carries on from here too, so it can't say which
+                    let saved = a.label();
+                    a.save(true, saved);
+                    a.print("SORRY, THE GAME COULDN'T BE SAVED.\n");
+                    a.jump(next);
+                    a.place(saved);
+                    a.print("OK.\n");
+                    a.jump(next);
+                });
+            }
+            if world.fixed(Fix::Score) {
+                a.when(Eq(Var(VERB), Num(code("SCO"))), |a| {
+                    a.run(r.score);
+                    a.jump(next);
+                });
+            }
+            a.when(Eq(Var(VERB), Num(code("GO"))), |a| {
+                a.print("GO WHERE?\n");
+                a.jump(acted);
+            });
+        });
+        verb(a, &["I", "INV"], true, r.inventory);
+        verb(a, &["L", "LOO"], true, r.look);
+        verb(a, &["EXA", "LOO"], false, r.examine);
+        verb(a, &["GET", "TAK"], false, r.get);
+        verb(a, &["DRO"], false, r.drop);
+        // everything else is specific to the book's adventure
+        if world.puzzles {
+            verb(a, &["OPE"], false, r.open);
+            verb(a, &["REA"], false, r.read_obj);
+            verb(a, &["POU"], false, r.pour);
+            verb(a, &["CLI"], false, r.climb);
+            verb(a, &["WAV"], false, r.wave);
+            verb(a, &["JUM"], false, r.jump);
+            verb(a, &["DIG"], false, r.dig);
+            verb(a, &["ROW"], false, r.row);
+            verb(a, &["LEA", "EXI"], false, r.leave);
+            verb(a, &["FIG"], false, r.fight);
+            verb(a, &["WEA"], false, r.wear);
+        }
+        a.place(dunno);
+        a.print("I DON'T KNOW HOW TO DO THAT.\n");
+        a.jump(next);
+
+        a.place(acted);
+        a.inc(MOVES);
+        a.place(next);
+        a.test(Zero(Var(WON)), true, top);
+        a.run(r.end);
+    }
+
+    /// Reading a line and working out its first two words, and line 3410.
+    fn input(&mut self) {
+        let (r, t) = (self.r, self.t);
+        let a = &mut self.asm;
+        a.routine(r.read, 0);
+        a.read(t.text, t.parse);
+        a.loadb(Num(t.parse), Num(1), WORDS);
+        a.call(r.word, &[Num(1)], VERB);
+        a.call(r.word, &[Num(2)], NOUN);
+        a.rtrue();
+
+        // the code of a word, or 0 if there aren't that many
+        a.routine(r.word, 6);
+        let (n, at, len, code, i, c) = (1, 2, 3, 4, 5, 6);
+        a.when(Gt(Var(n), Var(WORDS)), |a| a.ret(Num(0)));
+        // each word has 4 bytes in the parse buffer, after 2 of header
+        a.mul(Var(n), Num(4), at);
+        a.add(Var(at), Num(t.parse - 2), at);
+        a.loadb(Var(at), Num(2), len);
+        a.loadb(Var(at), Num(3), at);
+        a.add(Var(at), Num(t.text), at);
+        let (top, done) = (a.label(), a.label());
+        a.place(top);
+        a.test(Lt(Var(i), Var(len)), false, done);
+        a.test(Lt(Var(i), Num(3)), false, done);
+        a.loadb(Var(at), Var(i), c);
+        a.add(Var(c), Num(1u16.wrapping_sub(b'a' as u16)), c);
+        a.when(Lt(Var(c), Num(1)), |a| a.set(c, Num(31)));
+        a.when(Gt(Var(c), Num(26)), |a| a.set(c, Num(31)));
+        a.mul(Var(code), Num(32), code);
+        a.add(Var(code), Var(c), code);
+        a.inc(i);
+        a.jump(top);
+        a.place(done);
+        a.ret(Var(code));
+
+        // true for a yes
+        a.routine(r.sure, 1);
+        a.print("ARE YOU SURE? ");
+        a.run(r.read);
+        a.loadb(Num(t.text), Num(1), 1);
+        a.when(Eq(Var(1), Num(b'y' as u16)), |a| a.ret(Num(1)));
+        a.ret(Num(0));
+    }
+
+    /// Lines 500 to 700.
+    fn look(&mut self) {
+        let (world, r, t) = (self.world, self.r, self.t);
+        let a = &mut self.asm;
+        a.routine(r.look, 0);
+        a.new_line();
+        a.print("YOU ARE ");
+        a.loadw(Num(t.descs), Var(HERE), SP);
+        a.print_paddr(Var(SP));
+        a.new_line();
+        a.run(r.list_exits);
+        a.run(r.list_items);
+        a.rtrue();
+
+        a.routine(r.list_exits, 1);
+        let exits = 1;
+        a.mul(Var(HERE), Num(6), exits);
+        a.add(Var(exits), Num(t.exits), exits);
+        a.print("YOU CAN GO:");
+        for (i, name) in [(0, " NORTH"), (1, " SOUTH"), (2, " EAST"), (3, " WEST"), (5, " DOWN"), (4, " UP")] {
+            a.loadb(Var(exits), Num(i), SP);
+            a.when(NonZero(Var(SP)), |a| a.print(name));
+        }
+        a.rtrue();
+
+        a.routine(r.list_items, 3);
+        let (k, found, room) = (1, 2, 3);
+        a.new_line();
+        a.print("YOU CAN SEE:\n");
+        a.for_each(k, 1, world.objects.len() as u16 - 1, |a| {
+            a.loadb(Num(t.positions), Var(k), room);
+            a.and(Var(room), Num(127), room);
+            a.when(Eq(Var(room), Var(HERE)), |a| {
+                a.print("    ");
+                a.loadw(Num(t.names), Var(k), SP);
+                a.print_paddr(Var(SP));
+                a.new_line();
+                a.set(found, Num(1));
+            });
+        });
+        a.when(Zero(Var(found)), |a| a.print("    THERE IS NOTHING OF INTEREST HERE.\n"));
+        a.rtrue();
+    }
+
+    /// Lines 1000 to 1200.
+    fn subroutines(&mut self) {
+        let (r, t) = (self.r, self.t);
+        let last = self.world.objects.len() as u16 - 1;
+        let a = &mut self.asm;
+
+        // the object the noun names, or 0
+        a.routine(r.lookup, 1);
+        let k = 1;
+        a.for_each(k, 1, last, |a| {
+            a.loadw(Num(t.tags), Var(k), SP);
+            a.when(Eq(Var(SP), Var(NOUN)), |a| a.ret(Var(k)));
+        });
+        a.ret(Num(0));
+
+        // whether OBJ is here or carried
+        a.routine(r.here, 1);
+        let at = 1;
+        a.when(Zero(Var(OBJ)), |a| a.ret(Num(0)));
+        a.loadb(Num(t.positions), Var(OBJ), at);
+        a.when(Eq(Var(at), Num(INVENTORY as u16)), |a| a.ret(Num(1)));
+        a.and(Var(at), Num(127), at);
+        a.when(Eq(Var(at), Var(HERE)), |a| a.ret(Num(1)));
+        a.ret(Num(0));
+
+        // how many objects are carried
+        a.routine(r.count, 2);
+        let (k, carried) = (1, 2);
+        a.for_each(k, 1, last, |a| {
+            a.loadb(Num(t.positions), Var(k), SP);
+            a.when(Eq(Var(SP), Num(INVENTORY as u16)), |a| a.inc(carried));
+        });
+        a.ret(Var(carried));
+    }
+
+    /// Line 2000.
+    fn go(&mut self) {
+        let (world, r, t) = (self.world, self.r, self.t);
+        let a = &mut self.asm;
+        a.routine(r.go, 2);
+        let (dest, boat) = (1, 2);
+        let check = a.label();
+        a.when(Eq(Var(DIR), Num(7)), |a| {
+            // the boat might have drifted off
+            if world.puzzles {
+                a.add(Var(HERE), Num(128), boat);
+                a.loadb(Num(t.positions), Num(BOAT_OBJ as u16), SP);
+                a.when(Eq(Var(SP), Var(boat)), |a| a.set(dest, Num(BOAT_ROOM as u16)));
+            }
+            a.jump(check);
+        });
+        a.mul(Var(HERE), Num(6), dest);
+        a.add(Var(dest), Var(DIR), dest);
+        a.add(Var(dest), Num(t.exits - 1), dest);
+        a.loadb(Var(dest), Num(0), dest);
+        a.place(check);
+        if world.puzzles {
+            a.when(Eq(Var(dest), Num(GUARDED as u16)), |a| {
+                // 2410
+                a.loadb(Num(t.positions), Num(GUARD as u16), SP);
+                a.when(Eq(Var(SP), Num(immobile!(CASTLE_GATE) as u16)), |a| {
+                    a.print_ret("THE GUARD WON'T LET YOU!");
+                });
+                a.set(HERE, Num(NARROW_HALL as u16));
+                a.run(r.look);
+                a.rtrue();
+            });
+        }
+        let cant = a.label();
+        a.test(Zero(Var(dest)), true, cant);
+        a.test(Gt(Var(dest), Num(world.rooms.len() as u16 - 1)), true, cant);
+        a.set(HERE, Var(dest));
+        a.run(r.look);
+        a.rtrue();
+        a.place(cant);
+        a.print_ret("YOU CAN'T GO THERE!");
+    }
+
+    /// Lines 2500 to 3050, and SCORE.
+    fn generic_verbs(&mut self) {
+        let (world, r, t) = (self.world, self.r, self.t);
+        let last = world.objects.len() as u16 - 1;
+        let a = &mut self.asm;
+
+        // 2700
+        a.routine(r.inventory, 2);
+        let (k, found) = (1, 2);
+        a.when(NonZero(Var(GLOVED)), |a| a.print("YOU ARE WEARING RUBBER GLOVES.\n"));
+        a.print("YOU ARE CARRYING:\n");
+        a.for_each(k, 1, last, |a| {
+            a.loadb(Num(t.positions), Var(k), SP);
+            a.when(Eq(Var(SP), Num(INVENTORY as u16)), |a| {
+                a.print("    ");
+                a.loadw(Num(t.names), Var(k), SP);
+                a.print_paddr(Var(SP));
+                a.new_line();
+                a.set(found, Num(1));
+            });
+        });
+        a.when(Zero(Var(found)), |a| a.print("    NOTHING\n"));
+        a.rtrue();
+
+        // 2500
+        a.routine(r.get, 1);
+        let at = 1;
+        want(a, "GET");
+        a.call(r.lookup, &[], OBJ);
+        a.when(Zero(Var(OBJ)), |a| a.print_ret("YOU CAN'T GET THAT!"));
+        a.loadb(Num(t.positions), Var(OBJ), at);
+        a.when(Eq(Var(at), Num(INVENTORY as u16)), |a| a.print_ret("YOU ALREADY HAVE IT!"));
+        a.when(Gt(Var(at), Num(127)), |a| a.print_ret("YOU CAN'T GET THAT!"));
+        a.when(Ne(Var(at), Var(HERE)), |a| a.print_ret("THAT ISN'T HERE!"));
+        a.call(r.count, &[], SP);
+        a.when(Gt(Var(SP), Num(MAX_INVENTORY as u16 - 1)), |a| a.print_ret("YOU CAN'T CARRY ANY MORE."));
+        // 2575
+        if world.puzzles {
+            a.when(is_noun("RUB"), |a| {
+                award(a, world, 25);
+                a.set(WON, Num(1));
+                a.rtrue();
+            });
+        }
+        a.storeb(Num(t.positions), Var(OBJ), Num(INVENTORY as u16));
+        a.print_ret("TAKEN.");
+
+        // 2600
+        a.routine(r.drop, 0);
+        want(a, "DROP");
+        a.call(r.lookup, &[], OBJ);
+        a.when(NonZero(Var(OBJ)), |a| {
+            a.loadb(Num(t.positions), Var(OBJ), SP);
+            a.when(Eq(Var(SP), Num(INVENTORY as u16)), |a| {
+                a.storeb(Num(t.positions), Var(OBJ), Var(HERE));
+                a.print_ret("DROPPED.");
+            });
+        });
+        a.print_ret("YOU DON'T HAVE THAT!");
+
+        // 2900
+        a.routine(r.examine, 0);
+        want(a, "EXAMINE");
+        if world.puzzles {
+            a.when(is_noun("GRO"), |a| {
+                a.when(Ne(Var(HERE), Num(OPEN_FIELD as u16)), |a| a.print_ret("IT LOOKS LIKE GROUND!"));
+                a.loadb(Num(t.positions), Num(SWORD as u16), SP);
+                a.when(Zero(Var(SP)), |a| a.print_ret("IT LOOKS LIKE SOMETHING'S BURIED HERE."));
+                a.print_ret("THERE'S A HOLE HERE.");
+            });
+        }
+        need_here(a, r);
+        if world.puzzles {
+            a.when(is_noun("BOT"), |a| a.print_ret("THERE'S SOMETHING WRITTEN ON IT!"));
+            a.when(is_noun("CAS"), |a| a.print_ret("THERE'S A JEWEL INSIDE!"));
+            a.when(is_noun("BAR"), |a| a.print_ret("IT'S FILLED WITH RAINWATER."));
+        }
+        a.print_ret("YOU SEE NOTHING UNUSUAL.");
+
+        a.routine(r.score, 0);
+        a.print("YOU HAVE SCORED ");
+        a.print_num(Var(SCORE));
+        a.print(&format!(" OUT OF {} POINTS\nIN ", MAX_SCORE));
+        a.print_num(Var(MOVES));
+        a.print_ret(" MOVES.");
+    }
+
+    /// Line 3430, then our question or the book's.
+    fn end(&mut self) {
+        let (world, r, t) = (self.world, self.r, self.t);
+        let a = &mut self.asm;
+        a.routine(r.end, 1);
+        let ch = 1;
+        a.new_line();
+        a.print("CONGRATULATIONS! YOU'VE WON!\n");
+        if world.fixed(Fix::Score) {
+            a.run(r.score);
+            a.print("THAT EARNS YOU THE RANK OF\n");
+            let ranked = a.label();
+            for &(min, rank) in RANKS.iter() {
+                let line = format!("{}.\n", rank);
+                if min == 0 {
+                    a.print(&line);
+                    break;
+                }
+                a.when(Gt(Var(SCORE), Num(min as u16 - 1)), |a| {
+                    a.print(&line);
+                    a.jump(ranked);
+                });
+            }
+            a.place(ranked);
+        }
+
+        if !world.fixed(Fix::Replay) {
+            // 3440
+            a.new_line();
+            a.print("WOULD YOU LIKE TO PLAY AGAIN? ");
+            a.run(r.read);
+            a.loadb(Num(t.text), Num(1), ch);
+            a.when(Eq(Var(ch), Num(b'y' as u16)), |a| a.restart());
+            a.quit();
+            return;
+        }
+        // ours, with RESTORE
+        let ask = a.label();
+        a.place(ask);
+        a.new_line();
+        let save = world.fixed(Fix::Save);
+        a.print(if save { "WOULD YOU LIKE TO RESTART, RESTORE\nOR QUIT? " } else { "WOULD YOU LIKE TO RESTART OR QUIT? " });
+        a.run(r.read);
+        a.when(Eq(Var(VERB), Num(code("Q"))), |a| a.quit());
+        a.when(Eq(Var(VERB), Num(code("QUI"))), |a| a.quit());
+        a.test(Ne(Var(VERB), Num(code("RES"))), true, ask);
+        fifth_letter(a, t, ch);
+        a.when(Eq(Var(ch), Num(b'a' as u16)), |a| a.restart());
+        if !save {
+            a.jump(ask);
+            return;
+        }
+        a.test(Ne(Var(ch), Num(b'o' as u16)), true, ask);
+        let restored = a.label();
+        a.restore(true, restored);
+        a.print("THERE'S NO SAVED GAME TO RESTORE.\n");
+        a.place(restored);
+        a.jump(ask);
+    }
+
+    /// Lines 3500 to 4950, the book's own adventure.
+    fn puzzles(&mut self) {
+        let (world, r, t) = (self.world, self.r, self.t);
+        let id = |tag: &str| world.objects.iter().position(|obj| obj.tag == tag).unwrap_or(0) as u16;
+        let (tree, shovel, fan) = (id("TRE"), id("SHO"), id("FAN"));
+        let a = &mut self.asm;
+
+        // 3500
+        a.routine(r.read_obj, 0);
+        want(a, "READ");
+        need_here(a, r);
+        a.when(is_noun("DIA"), |a| {
+            a.print("IT SAYS: 'ADD SODIUM CHLORIDE PLUS THE\n");
+            a.print("FORMULA TO RAINWATER, TO REACH THE\n");
+            a.print_ret("OTHER WORLD.'");
+        });
+        a.when(is_noun("DIC"), |a| {
+            a.print("IT SAYS: SODIUM CHLORIDE IS\n");
+            a.print_ret("COMMON TABLE SALT.");
+        });
+        a.when(is_noun("BOT"), |a| a.print_ret("IT READS: 'SECRET FORMULA'."));
+        a.print_ret("YOU CAN'T READ THAT!");
+
+        // 3700
+        a.routine(r.open, 0);
+        want(a, "OPEN");
+        need_here(a, r);
+        let fall_out = |a: &mut Asm, obj: usize, already: &str, found: &str, once: bool| {
+            if once {
+                a.loadb(Num(t.positions), Num(obj as u16), SP);
+                a.when(NonZero(Var(SP)), |a| a.print_ret(already));
+            }
+            a.storeb(Num(t.positions), Num(obj as u16), Var(HERE));
+            a.print(found);
+            a.run(r.list_items);
+            a.rtrue();
+        };
+        a.when(is_noun("BOX"), |a| {
+            // 3740, unless we don't let the box respawn the bottle
+            let once = world.fixed(Fix::Box);
+            fall_out(a, BOTTLE, "THE BOX IS ALREADY OPEN.", "SOMETHING FELL OUT!\n", once);
+        });
+        a.when(is_noun("CAB"), |a| {
+            fall_out(a, SALT, "THE CABINET IS ALREADY OPEN.", "THERE'S SOMETHING INSIDE!\n", true);
+        });
+        a.when(is_noun("CAS"), |a| {
+            a.loadb(Num(t.positions), Num(RUBY as u16), SP);
+            a.when(NonZero(Var(SP)), |a| a.print_ret("THE CASE IS ALREADY OPEN."));
+            a.when(Zero(Var(GLOVED)), |a| a.print_ret("THE CASE IS ELECTRIFIED!"));
+            a.print("THE GLOVES INSULATE AGAINST THE\n");
+            a.print("ELECTRICITY! THE CASE OPENS!\n");
+            a.storeb(Num(t.positions), Num(RUBY as u16), Var(HERE));
+            award(a, world, 15);
+            a.run(r.list_items);
+            a.rtrue();
+        });
+        a.print_ret("YOU CAN'T OPEN THAT!");
+
+        // 3900
+        a.routine(r.pour, 0);
+        want(a, "POUR");
+        need_here(a, r);
+        for &(tag, flag, empty) in &[("SAL", SALTED, "THE SALT SHAKER IS EMPTY."), ("BOT", FORMULATED, "THE BOTTLE IS EMPTY.")] {
+            a.when(is_noun(tag), |a| {
+                a.when(NonZero(Var(flag)), |a| a.print_ret(empty));
+                a.when(Eq(Var(HERE), Num(GARAGE as u16)), |a| {
+                    a.set(flag, Num(1));
+                    a.run(r.poured);
+                    a.rtrue();
+                });
+            });
+        }
+        a.print_ret("YOU CAN'T POUR THAT!");
+
+        a.routine(r.poured, 0);
+        a.print("POURED!\n");
+        a.when(Zero(Var(SALTED)), |a| a.rtrue());
+        a.when(Zero(Var(FORMULATED)), |a| a.rtrue());
+        // 4010
+        a.print("THERE IS AN EXPLOSION!\nEVERYTHING GOES BLACK!\n");
+        a.print("SUDDENLY YOU ARE. . .\n. . .SOMEWHERE ELSE!\n");
+        a.set(HERE, Num(OPEN_FIELD as u16));
+        award(a, world, 25);
+        a.run(r.look);
+        a.rtrue();
+
+        // 4100
+        a.routine(r.climb, 0);
+        want(a, "CLIMB");
+        a.when(is_noun("TRE"), |a| {
+            let tree_here = here(a, r, tree);
+            a.when(NonZero(tree_here), |a| a.print_ret("YOU CAN'T REACH THE BRANCHES!"));
+        });
+        a.when(is_noun("LAD"), |a| {
+            let ladder_here = here(a, r, LADDER as u16);
+            a.when(NonZero(ladder_here), |a| {
+                // 4150
+                a.when(Ne(Var(HERE), Num(FOREST_EDGE as u16)), |a| a.print_ret("WHATEVER FOR?"));
+                a.print("THE LADDER SINKS UNDER YOUR WEIGHT!\n");
+                a.storeb(Num(t.positions), Num(LADDER as u16), Num(0));
+                a.print_ret("IT DISAPPEARS INTO THE GROUND!");
+            });
+        });
+        a.print_ret("IT WON'T DO ANY GOOD.");
+
+        // 4300
+        a.routine(r.jump, 0);
+        for &(from, grab, to) in &[(FOREST_EDGE, "THE LOWEST", TREE_BRANCH), (TREE_BRANCH, "A HIGHER", TREE_TOP)] {
+            a.when(Eq(Var(HERE), Num(from as u16)), |a| {
+                a.print(&format!("YOU GRAB {} BRANCH OF THE\n", grab));
+                a.print("TREE AND PULL YOURSELF UP. . . .\n");
+                a.set(HERE, Num(to as u16));
+                a.run(r.look);
+                a.rtrue();
+            });
+        }
+        a.print_ret("WHEE! THAT WAS FUN!");
+
+        // 4400
+        a.routine(r.dig, 0);
+        a.when(Eq(Var(WORDS), Num(1)), |a| a.set(NOUN, Num(code("GRO"))));
+        a.when(Ne(Var(NOUN), Num(code("GRO"))), |a| {
+            a.when(Ne(Var(NOUN), Num(code("HOL"))), |a| a.print_ret("YOU CAN'T DIG THAT!"));
+        });
+        let shovel_here = here(a, r, shovel);
+        a.when(Zero(shovel_here), |a| a.print_ret("YOU DON'T HAVE A SHOVEL!"));
+        a.when(Ne(Var(HERE), Num(OPEN_FIELD as u16)), |a| a.print_ret("YOU DON'T FIND ANYTHING."));
+        a.loadb(Num(t.positions), Num(SWORD as u16), SP);
+        a.when(NonZero(Var(SP)), |a| a.print_ret("THERE'S NOTHING ELSE THERE!"));
+        a.print("THERE'S SOMETHING THERE!\n");
+        a.storeb(Num(t.positions), Num(SWORD as u16), Num(OPEN_FIELD as u16));
+        award(a, world, 15);
+        a.run(r.list_items);
+        a.rtrue();
+
+        // 4500
+        a.routine(r.row, 0);
+        a.when(Eq(Var(WORDS), Num(2)), |a| {
+            a.when(Ne(Var(NOUN), Num(code("BOA"))), |a| a.print_ret("HOW CAN YOU ROW THAT?"));
+        });
+        a.when(Ne(Var(HERE), Num(BOAT_ROOM as u16)), |a| a.print_ret("YOU'RE NOT IN A BOAT!"));
+        a.print_ret("YOU DON'T HAVE AN OAR!");
+
+        // 4600
+        a.routine(r.wave, 0);
+        want(a, "WAVE");
+        a.when(Ne(Var(NOUN), Num(code("FAN"))), |a| a.print_ret("YOU CAN'T WAVE THAT!"));
+        let fan_here = here(a, r, fan);
+        a.when(Zero(fan_here), |a| a.print_ret("YOU DON'T HAVE A FAN!"));
+        a.when(Ne(Var(HERE), Num(BOAT_ROOM as u16)), |a| a.print_ret("YOU FEEL A REFRESHING BREEZE!"));
+        a.print("A POWERFUL BREEZE PROPELS THE BOAT\nTO THE OPPOSITE SHORE!\n");
+        a.loadb(Num(t.positions), Num(BOAT_OBJ as u16), SP);
+        a.when(Eq(Var(SP), Num(immobile!(SOUTH_BANK) as u16)), |a| {
+            a.storeb(Num(t.positions), Num(BOAT_OBJ as u16), Num(immobile!(NORTH_BANK) as u16));
+            a.rtrue();
+        });
+        a.storeb(Num(t.positions), Num(BOAT_OBJ as u16), Num(immobile!(SOUTH_BANK) as u16));
+        a.rtrue();
+
+        // 4700
+        a.routine(r.leave, 0);
+        a.when(Eq(Var(WORDS), Num(1)), |a| a.set(NOUN, Num(code("BOA"))));
+        a.when(Ne(Var(HERE), Num(BOAT_ROOM as u16)), |a| a.print_ret("PLEASE GIVE A DIRECTION!"));
+        a.when(Ne(Var(NOUN), Num(code("BOA"))), |a| a.print_ret("HUH?"));
+        a.loadb(Num(t.positions), Num(BOAT_OBJ as u16), SP);
+        a.and(Var(SP), Num(127), HERE);
+        a.run(r.look);
+        a.rtrue();
+
+        // 4800
+        a.routine(r.fight, 0);
+        a.when(Eq(Var(WORDS), Num(1)), |a| a.print_ret("WHOM DO YOU WANT TO FIGHT?"));
+        a.when(Ne(Var(NOUN), Num(code("GUA"))), |a| a.print_ret("YOU CAN'T FIGHT THEM!"));
+        let guard_here = here(a, r, GUARD as u16);
+        a.when(Zero(guard_here), |a| a.print_ret("THERE'S NO GUARD HERE!"));
+        a.loadb(Num(t.positions), Num(SWORD as u16), SP);
+        a.when(Ne(Var(SP), Num(INVENTORY as u16)), |a| a.print_ret("YOU DON'T HAVE A WEAPON!"));
+        a.print("THE GUARD, NOTICING YOUR SWORD,\nWISELY RETREATS INTO THE CASTLE.\n");
+        a.storeb(Num(t.positions), Num(GUARD as u16), Num(0)); // 4850
+        award(a, world, 20);
+        a.rtrue();
+
+        // 4900
+        a.routine(r.wear, 0);
+        want(a, "WEAR");
+        a.when(Ne(Var(NOUN), Num(code("GLO"))), |a| a.print_ret("YOU CAN'T WEAR THAT!"));
+        a.when(NonZero(Var(GLOVED)), |a| a.print_ret("YOU ARE ALREADY WEARING THE RUBBER GLOVES."));
+        let gloves_here = here(a, r, GLOVES as u16);
+        a.when(Zero(gloves_here), |a| a.print_ret("YOU DON'T HAVE THE GLOVES."));
+        a.set(GLOVED, Num(1));
+        a.storeb(Num(t.positions), Num(GLOVES as u16), Num(0));
+        a.print_ret("YOU ARE NOW WEARING THE GLOVES.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::WALKTHROUGH;
+    use crate::{Session, STOCK};
+
+    /// Just enough of a version 3 interpreter to play what `compile` writes.
+    struct Machine {
+        mem: Vec<u8>,
+        pc: usize,
+        stack: Vec<u16>,
+        frames: Vec<Frame>,
+        input: Vec<&'static str>,
+        out: String
+    }
+
+    struct Frame {
+        locals: Vec<u16>,
+        base: usize,
+        pc: usize,
+        store: u8
+    }
+
+    impl Machine {
+        fn new(story: Vec<u8>, input: &[&'static str]) -> Self {
+            let pc = u16::from_be_bytes([story[6], story[7]]) as usize;
+            let top = Frame { locals: Vec::new(), base: 0, pc: 0, store: 0 };
+            Machine { mem: story, pc, stack: Vec::new(), frames: vec![top], input: input.to_vec(), out: String::new() }
+        }
+
+        fn word(&self, at: usize) -> u16 {
+            u16::from_be_bytes([self.mem[at], self.mem[at + 1]])
+        }
+
+        fn fetch(&mut self) -> u8 {
+            self.pc += 1;
+            self.mem[self.pc - 1]
+        }
+
+        fn fetch_word(&mut self) -> u16 {
+            self.pc += 2;
+            self.word(self.pc - 2)
+        }
+
+        fn global(&self, var: u8) -> usize {
+            self.word(0x0c) as usize + 2 * (var as usize - 0x10)
+        }
+
+        fn read(&mut self, var: u8) -> u16 {
+            match var {
+                0 => self.stack.pop().expect("stack underflow"),
+                1..=15 => self.frames.last().unwrap().locals[var as usize - 1],
+                _ => self.word(self.global(var))
+            }
+        }
+
+        fn write(&mut self, var: u8, value: u16) {
+            match var {
+                0 => self.stack.push(value),
+                1..=15 => self.frames.last_mut().unwrap().locals[var as usize - 1] = value,
+                _ => {
+                    let at = self.global(var);
+                    self.mem[at..at + 2].copy_from_slice(&value.to_be_bytes());
+                }
+            }
+        }
+
+        /// Variables named by an operand leave the stack where it is.
+        fn write_in_place(&mut self, var: u8, value: u16) {
+            if var == 0 {
+                self.stack.pop();
+            }
+            self.write(var, value);
+        }
+
+        fn read_in_place(&mut self, var: u8) -> u16 {
+            let value = self.read(var);
+            if var == 0 {
+                self.stack.push(value);
+            }
+            value
+        }
+
+        fn operand(&mut self, kind: u8) -> u16 {
+            match kind {
+                0 => self.fetch_word(),
+                1 => self.fetch() as u16,
+                _ => {
+                    let var = self.fetch();
+                    self.read(var)
+                }
+            }
+        }
+
+        fn branch(&mut self, passed: bool) {
+            let first = self.fetch();
+            let offset = if first & 0x40 != 0 {
+                (first & 0x3f) as i16
+            } else {
+                let offset = ((first as u16 & 0x3f) << 8 | self.fetch() as u16) as i16;
+                offset << 2 >> 2
+            };
+            if passed == (first & 0x80 != 0) {
+                match offset {
+                    0 | 1 => self.ret(offset as u16),
+                    _ => self.pc = (self.pc as isize + offset as isize - 2) as usize
+                }
+            }
+        }
+
+        fn ret(&mut self, value: u16) {
+            let frame = self.frames.pop().unwrap();
+            self.stack.truncate(frame.base);
+            self.pc = frame.pc;
+            self.write(frame.store, value);
+        }
+
+        /// The text at `at`, and how many bytes it took.
+        fn text(&self, at: usize) -> (String, usize) {
+            let mut zchars = Vec::new();
+            let mut len = 0;
+            loop {
+                let word = self.word(at + len);
+                len += 2;
+                zchars.extend([(word >> 10) as u8 & 31, (word >> 5) as u8 & 31, word as u8 & 31]);
+                if word & 0x8000 != 0 {
+                    break;
+                }
+            }
+            let mut text = String::new();
+            let (mut shift, mut zchars) = (0, zchars.into_iter());
+            while let Some(z) = zchars.next() {
+                match (shift, z) {
+                    (_, 0) => text.push(' '),
+                    (_, 4) => { shift = 1; continue; }
+                    (_, 5) => { shift = 2; continue; }
+                    (2, 6) => {
+                        let (high, low) = (zchars.next().unwrap_or(0), zchars.next().unwrap_or(0));
+                        text.push((high << 5 | low) as char);
+                    }
+                    (2, 7) => text.push('\n'),
+                    (2, _) => text.push(A2.as_bytes()[z as usize - 8] as char),
+                    (1, _) => text.push((b'A' + z - 6) as char),
+                    (_, 1..=3) => panic!("no abbreviations"),
+                    _ => text.push((b'a' + z - 6) as char)
+                }
+                shift = 0;
+            }
+            (text, len)
+        }
+
+        /// Runs until the story quits or asks for more than the input has.
+        fn run(&mut self) -> &str {
+            loop {
+                let op = self.fetch();
+                let (count, opcode, ops) = match op {
+                    0x80..=0xbf if op & 0x30 == 0x30 => (0, op & 0x0f, Vec::new()),
+                    0x80..=0xbf => (1, op & 0x0f, vec![self.operand(op >> 4 & 3)]),
+                    0xc0..=0xff => {
+                        let kinds = self.fetch();
+                        let kinds: Vec<u8> = (0..4).map(|i| kinds >> (6 - 2 * i) & 3).take_while(|&k| k != 3).collect();
+                        let ops = kinds.into_iter().map(|kind| self.operand(kind)).collect();
+                        (if op & 0x20 == 0 { 2 } else { 4 }, op & 0x1f, ops)
+                    }
+                    _ => panic!("long form {:02x} at {:x}", op, self.pc - 1)
+                };
+                match (count, opcode) {
+                    (2, 0x01) => self.branch(ops[1..].contains(&ops[0])),
+                    (2, 0x02) => self.branch((ops[0] as i16) < ops[1] as i16),
+                    (2, 0x03) => self.branch(ops[0] as i16 > ops[1] as i16),
+                    (2, 0x05) => {
+                        let value = self.read_in_place(ops[0] as u8).wrapping_add(1);
+                        self.write_in_place(ops[0] as u8, value);
+                        self.branch(value as i16 > ops[1] as i16);
+                    }
+                    (2, 0x09) => self.store(ops[0] & ops[1]),
+                    (2, 0x0d) => self.write_in_place(ops[0] as u8, ops[1]),
+                    (2, 0x0f) => self.store(self.word(ops[0] as usize + 2 * ops[1] as usize)),
+                    (2, 0x10) => self.store(self.mem[ops[0] as usize + ops[1] as usize] as u16),
+                    (2, 0x14) => self.store(ops[0].wrapping_add(ops[1])),
+                    (2, 0x16) => self.store(ops[0].wrapping_mul(ops[1])),
+                    (1, 0x00) => self.branch(ops[0] == 0),
+                    (1, 0x05) => {
+                        let value = self.read_in_place(ops[0] as u8).wrapping_add(1);
+                        self.write_in_place(ops[0] as u8, value);
+                    }
+                    (1, 0x0b) => self.ret(ops[0]),
+                    (1, 0x0c) => self.pc = (self.pc as isize + ops[0] as i16 as isize - 2) as usize,
+                    (1, 0x0d) => self.out += &self.text(2 * ops[0] as usize).0,
+                    (0, 0x00) => self.ret(1),
+                    (0, 0x02) | (0, 0x03) => {
+                        let (text, len) = self.text(self.pc);
+                        self.pc += len;
+                        self.out += &text;
+                        if opcode == 0x03 {
+                            self.out.push('\n');
+                            self.ret(1);
+                        }
+                    }
+                    (0, 0x05) | (0, 0x06) => self.branch(false), // nowhere to save to
+                    (0, 0x09) => { self.stack.pop(); }
+                    (0, 0x0a) => return &self.out,
+                    (0, 0x0b) => self.out.push('\n'),
+                    (4, 0x00) => {
+                        let store = self.fetch();
+                        let routine = 2 * ops[0] as usize;
+                        let n = self.mem[routine] as usize;
+                        let mut locals: Vec<u16> = (0..n).map(|i| self.word(routine + 1 + 2 * i)).collect();
+                        for (local, &arg) in locals.iter_mut().zip(&ops[1..]) {
+                            *local = arg;
+                        }
+                        self.frames.push(Frame { locals, base: self.stack.len(), pc: self.pc, store });
+                        self.pc = routine + 1 + 2 * n;
+                    }
+                    (4, 0x02) => self.mem[ops[0] as usize + ops[1] as usize] = ops[2] as u8,
+                    (4, 0x04) => {
+                        if self.input.is_empty() {
+                            return &self.out;
+                        }
+                        let line = self.input.remove(0).to_ascii_lowercase();
+                        self.out += &line;
+                        self.out.push('\n');
+                        self.sread(ops[0] as usize, ops[1] as usize, &line);
+                    }
+                    (4, 0x06) => self.out += &(ops[0] as i16).to_string(),
+                    _ => panic!("no {}OP {:x} at {:x}", count, opcode, self.pc)
+                }
+            }
+        }
+
+        fn store(&mut self, value: u16) {
+            let var = self.fetch();
+            self.write(var, value);
+        }
+
+        /// Puts the line in the text buffer, and where its words are in the parse buffer.
+        fn sread(&mut self, text: usize, parse: usize, line: &str) {
+            let line = &line.as_bytes()[..line.len().min(self.mem[text] as usize - 1)];
+            self.mem[text + 1..text + 1 + line.len()].copy_from_slice(line);
+            self.mem[text + 1 + line.len()] = 0;
+            let mut words = 0;
+            let mut start = 0;
+            for word in line.split(|&c| c == b' ') {
+                if !word.is_empty() && words < self.mem[parse] as usize {
+                    let at = parse + 2 + 4 * words;
+                    self.mem[at..at + 4].copy_from_slice(&[0, 0, word.len() as u8, 1 + start as u8]);
+                    words += 1;
+                }
+                start += word.len() + 1;
+            }
+            self.mem[parse + 1] = words as u8;
+        }
+    }
+
+    fn played(world: &World, input: &[&'static str]) -> String {
+        Machine::new(compile(world), input).run().to_string()
+    }
+
+    #[test]
+    fn encodes_text_three_characters_to_a_word() {
+        // h i, then padding, with the top bit marking the end
+        assert_eq!(encode("hi"), [0xb5, 0xc5]);
+        // shift to A1 for a capital, and to A2 for punctuation
+        assert_eq!(encode("A!"), [0x10, 0xc5, 0xd0, 0xa5]);
+        assert_eq!(encode(""), [0x94, 0xa5]);
+    }
+
+    #[test]
+    fn codes_words_like_the_parser() {
+        assert_eq!(code("GO"), 7 * 32 + 15);
+        assert_eq!(code("north"), code("NOR"));
+        assert_eq!(code("N"), 14);
+        assert_eq!(code("É"), 31);
+    }
+
+    #[test]
+    fn the_header_describes_the_story() {
+        let story = compile(&STOCK);
+        let word = |at: usize| u16::from_be_bytes([story[at], story[at + 1]]) as usize;
+        assert_eq!(story[0], 3);
+        assert_eq!(word(0x1a) * 2, story.len());
+        let sum = story[0x40..].iter().map(|&b| b as usize).sum::<usize>() % 0x10000;
+        assert_eq!(word(0x1c), sum);
+        assert!(word(0x04) <= word(0x06) && word(0x06) < story.len());
+        assert_eq!(&story[0x12..0x18], b"860101");
+    }
+
+    #[test]
+    fn the_story_plays_the_walkthrough_to_a_win() {
+        let out = played(&STOCK, &WALKTHROUGH);
+        assert!(out.starts_with("\nALL YOUR LIFE YOU HAD HEARD THE STORIES\n"));
+        assert!(out.contains("SOMETHING FELL OUT!\n"));
+        assert!(out.contains("CONGRATULATIONS! YOU'VE WON!\n"));
+    }
+
+    #[test]
+    fn the_story_counts_moves_as_the_port_does() {
+        let mut commands = vec!["SCORE", "XYZZY", "", "LOOK", "GET NOTHING", "N", "SCORE", "Q", "N"];
+        commands.extend_from_slice(&WALKTHROUGH[..12]);
+        commands.push("SCORE");
+        let mut session = Session::new(&STOCK, true);
+        let port: String = commands.iter().map(|command| session.step(command)).collect();
+        let scores = |out: &str| -> Vec<String> {
+            out.replace('\n', " ").split("YOU HAVE SCORED ").skip(1)
+                .map(|score| score.split(" MOVES.").next().unwrap().to_string())
+                .collect()
+        };
+        let story = played(&STOCK, &commands);
+        assert_eq!(scores(&story), ["0 OUT OF 100 POINTS IN 0", "0 OUT OF 100 POINTS IN 3", "0 OUT OF 100 POINTS IN 13", "0 OUT OF 100 POINTS IN 14"]);
+        assert_eq!(scores(&story), scores(&port));
+    }
+}